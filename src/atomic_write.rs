@@ -0,0 +1,200 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether saving keeps a copy of the previous version of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Backup {
+	#[default]
+	Off,
+	/// Keeps the previous version as `file~`, replacing older backups.
+	Tilde,
+	/// Keeps every previous version as `file.YYYY-MM-DD-HHMMSS~`.
+	Timestamped,
+}
+
+impl Backup {
+	pub const ALL: [Backup; 3] = [Backup::Off, Backup::Tilde, Backup::Timestamped];
+
+	fn path(self, target: &Path) -> Option<PathBuf> {
+		let mut name = target.file_name()?.to_os_string();
+
+		match self {
+			Backup::Off => return None,
+			Backup::Tilde => name.push("~"),
+			Backup::Timestamped => name.push(format!(".{}~", timestamp(SystemTime::now()))),
+		}
+
+		Some(target.with_file_name(name))
+	}
+}
+
+impl fmt::Display for Backup {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Backup::Off => write!(f, "Off"),
+			Backup::Tilde => write!(f, "Keep the last version (file~)"),
+			Backup::Timestamped => write!(f, "Keep every version (timestamped)"),
+		}
+	}
+}
+
+/// Numbers the temporary files, so that overlapping saves of the same file,
+/// e.g. an autosave and a manual save, don't collide.
+static NEXT_TEMPORARY: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces the contents of a file without ever leaving it half written.
+///
+/// The bytes go to a temporary file next to the target, which is synced to
+/// disk and then renamed over the target. Symbolic links are followed, so the
+/// file they point to gets replaced, and the permissions of the original file
+/// are kept.
+pub async fn write(path: PathBuf, bytes: Vec<u8>, backup: Backup) -> io::Result<()> {
+	tokio::task::spawn_blocking(move || write_blocking(&path, &bytes, backup))
+		.await
+		.map_err(io::Error::other)?
+}
+
+fn write_blocking(path: &Path, bytes: &[u8], backup: Backup) -> io::Result<()> {
+	let target = match fs::canonicalize(path) {
+		Ok(target) => target,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+		Err(error) => return Err(error),
+	};
+
+	let original = fs::metadata(&target).ok();
+
+	let directory = target
+		.parent()
+		.filter(|directory| !directory.as_os_str().is_empty())
+		.unwrap_or(Path::new("."));
+
+	let name = target
+		.file_name()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "The path has no file name"))?;
+
+	let mut temporary_name = std::ffi::OsString::from(".");
+	temporary_name.push(name);
+	temporary_name.push(format!(".{}.{}.tmp", std::process::id(), NEXT_TEMPORARY.fetch_add(1, Ordering::Relaxed)));
+
+	let temporary = directory.join(temporary_name);
+
+	let result = (|| {
+		let mut file = OpenOptions::new()
+			.write(true)
+			.create_new(true)
+			.open(&temporary)?;
+
+		file.write_all(bytes)?;
+
+		if let Some(original) = &original {
+			file.set_permissions(original.permissions())?;
+
+			#[cfg(unix)]
+			{
+				use std::os::unix::fs::MetadataExt;
+
+				// Only possible with enough privileges, e.g. when editing another user's file as root
+				let _ = std::os::unix::fs::fchown(&file, Some(original.uid()), Some(original.gid()));
+			}
+		}
+
+		file.sync_all()?;
+
+		if original.is_some() {
+			if let Some(backup) = backup.path(&target) {
+				fs::copy(&target, backup)?;
+			}
+		}
+
+		fs::rename(&temporary, &target)?;
+
+		// Makes the rename itself durable
+		#[cfg(unix)]
+		if let Ok(directory) = File::open(directory) {
+			let _ = directory.sync_all();
+		}
+
+		Ok(())
+	})();
+
+	if result.is_err() {
+		let _ = fs::remove_file(&temporary);
+	}
+
+	result
+}
+
+/// Formats a time as `YYYY-MM-DD-HHMMSS` in UTC.
+fn timestamp(time: SystemTime) -> String {
+	let seconds = time
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs());
+
+	let days = i64::try_from(seconds / 86_400).unwrap_or_default();
+	let time_of_day = seconds % 86_400;
+
+	// Converts days since the epoch to a civil date, see
+	// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let day_of_era = z.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+	format!(
+		"{year:04}-{month:02}-{day:02}-{:02}{:02}{:02}",
+		time_of_day / 3600,
+		time_of_day / 60 % 60,
+		time_of_day % 60,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn overlapping_writes_of_one_file_succeed() {
+		let directory = std::env::temp_dir().join(format!("atomic_write_test_{}", std::process::id()));
+		fs::create_dir_all(&directory).unwrap();
+
+		let path = directory.join("file.txt");
+
+		let writers: Vec<_> = (0..8)
+			.map(|index| {
+				let path = path.clone();
+
+				std::thread::spawn(move || write_blocking(&path, format!("{index}").as_bytes(), Backup::Off))
+			})
+			.collect();
+
+		for writer in writers {
+			writer.join().unwrap().unwrap();
+		}
+
+		let text = fs::read_to_string(&path).unwrap();
+		let leftovers = fs::read_dir(&directory).unwrap().count();
+
+		fs::remove_dir_all(&directory).unwrap();
+
+		assert!(text.parse::<usize>().is_ok_and(|index| index < 8));
+		assert_eq!(leftovers, 1);
+	}
+
+	#[test]
+	fn timestamp_is_utc_date_and_time() {
+		let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+		assert_eq!(timestamp(time), "2023-11-14-221320");
+	}
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CONFIG_PATH;
+use crate::format::TextFormat;
+
+/// How often the autosave timer checks for files to save.
+pub const TICK: Duration = Duration::from_secs(1);
+
+pub const DEFAULT_DELAY: u64 = 30;
+
+/// The delays, in seconds, that can be picked in the settings.
+pub const DELAYS: [u64; 5] = [5, 15, 30, 60, 300];
+
+#[must_use]
+pub fn default_delay() -> u64 {
+	DEFAULT_DELAY
+}
+
+/// Where every running editor keeps the swap files of its unsaved tabs.
+pub static RECOVERY_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_PATH.with_file_name("recovery"));
+
+static NEXT_SWAP: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Mode {
+	#[default]
+	Off,
+	/// Saves modified files that have a path.
+	SaveFiles,
+	/// Writes swap files of modified tabs, which are recovered after a crash.
+	SwapFiles,
+}
+
+impl Mode {
+	pub const ALL: [Mode; 3] = [Mode::Off, Mode::SaveFiles, Mode::SwapFiles];
+}
+
+impl fmt::Display for Mode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Mode::Off => write!(f, "Off"),
+			Mode::SaveFiles => write!(f, "Save files"),
+			Mode::SwapFiles => write!(f, "Write recovery files"),
+		}
+	}
+}
+
+/// A snapshot of a tab with unsaved changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swap {
+	pub path: Option<PathBuf>,
+	pub text: String,
+	pub format: TextFormat,
+	pub cursor: (usize, usize),
+}
+
+/// The recovery folder of this editor, locked for as long as it runs so other
+/// editors can tell its swap files apart from ones left behind by a crash.
+pub struct Recovery {
+	directory: PathBuf,
+	_lock: Option<File>,
+}
+
+impl Recovery {
+	pub fn new() -> Self {
+		// Process ids get reused, so the start time keeps the folder unique
+		let started = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_millis();
+
+		let directory = RECOVERY_PATH.join(format!("{}-{started}", std::process::id()));
+
+		// The lock is taken before the folder exists, so other editors never
+		// see the folder unlocked and take it for one left behind
+		let lock = fs::create_dir_all(&*RECOVERY_PATH)
+			.and_then(|()| File::create(lock_path(&directory)))
+			.and_then(|lock| lock.lock().map(|()| lock))
+			.and_then(|lock| fs::create_dir(&directory).map(|()| lock));
+
+		if let Err(error) = &lock {
+			eprintln!("Failed to create recovery folder: {error}");
+		}
+
+		Recovery {
+			directory,
+			_lock: lock.ok(),
+		}
+	}
+
+	/// Writes a swap file, returning its path.
+	pub fn write(&self, existing: Option<&Path>, swap: &Swap) -> Option<PathBuf> {
+		let path = existing.map_or_else(
+			|| {
+				self.directory
+					.join(format!("{}.swap", NEXT_SWAP.fetch_add(1, Ordering::Relaxed)))
+			},
+			Path::to_path_buf,
+		);
+
+		let result = serde_json::to_vec(swap)
+			.map_err(std::io::Error::from)
+			.and_then(|bytes| fs::write(&path, bytes));
+
+		match result {
+			Ok(()) => Some(path),
+			Err(error) => {
+				eprintln!("Failed to write recovery file: {error}");
+				None
+			}
+		}
+	}
+
+	/// Removes the recovery folder when the editor closes normally.
+	pub fn remove(&self) {
+		remove_directory(&self.directory);
+	}
+}
+
+pub fn remove_swap(path: &Path) {
+	let _ = fs::remove_file(path);
+}
+
+/// The lock of a recovery folder, which sits next to the folder.
+fn lock_path(directory: &Path) -> PathBuf {
+	directory.with_extension("lock")
+}
+
+fn remove_directory(directory: &Path) {
+	let _ = fs::remove_dir_all(directory);
+	let _ = fs::remove_file(lock_path(directory));
+}
+
+/// Swap files left behind by editors that are no longer running.
+#[derive(Debug, Clone, Default)]
+pub struct Leftovers {
+	pub directories: Vec<PathBuf>,
+	pub swaps: Vec<Swap>,
+}
+
+impl Leftovers {
+	pub fn find() -> Self {
+		let mut leftovers = Leftovers::default();
+
+		let Ok(entries) = fs::read_dir(&*RECOVERY_PATH) else {
+			return leftovers;
+		};
+
+		let directories = entries
+			.filter_map(Result::ok)
+			.map(|entry| entry.path())
+			.filter(|path| path.is_dir());
+
+		for directory in directories {
+			// Editors that are still running hold the lock
+			let abandoned = File::open(lock_path(&directory))
+				.map_or(true, |lock| lock.try_lock().is_ok());
+
+			if !abandoned {
+				continue;
+			}
+
+			if let Ok(swaps) = fs::read_dir(&directory) {
+				leftovers.swaps.extend(
+					swaps
+						.filter_map(Result::ok)
+						.filter(|swap| swap.path().extension().is_some_and(|extension| extension == "swap"))
+						.filter_map(|swap| fs::read(swap.path()).ok())
+						.filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+				);
+			}
+
+			leftovers.directories.push(directory);
+		}
+
+		leftovers
+	}
+
+	/// Deletes the swap files once they were recovered or discarded.
+	pub fn remove(&self) {
+		for directory in &self.directories {
+			remove_directory(directory);
+		}
+	}
+}
@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+
+use iced::Theme;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, THEMES};
+
+pub const USAGE: &str = "\
+Usage: multi_tab_text_editor [OPTIONS] [FILE[:LINE[:COLUMN]]]...
+
+On Linux and macOS, files are opened in the editor that is already running,
+unless any options are given.
+
+Options:
+      --new-window     Open a new window, without restoring the previous session
+      --readonly       Open the given files as read-only
+      --theme <THEME>  Use the given theme, e.g. \"Dracula\" or \"theme.dracula\"
+      --config <PATH>  Read and write the configuration at the given path
+  -h, --help           Print this help";
+
+/// A file given on the command line, with the position to put the cursor at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileArgument {
+	pub path: PathBuf,
+	/// The zero-based line and column, which counts characters.
+	pub position: (usize, usize),
+}
+
+impl FileArgument {
+	/// The cursor position in the text of the file, with the column in bytes
+	/// like the editor counts it.
+	pub fn position_in(&self, text: &str) -> (usize, usize) {
+		let (line, column) = self.position;
+
+		let column = match text.split('\n').nth(line) {
+			Some(current) => current
+				.char_indices()
+				.nth(column)
+				.map_or(current.len(), |(offset, _)| offset),
+			None => column,
+		};
+
+		(line, column)
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Args {
+	pub help: bool,
+	pub files: Vec<FileArgument>,
+	pub new_window: bool,
+	pub readonly: bool,
+	pub theme: Option<Theme>,
+	pub config: Option<PathBuf>,
+	/// Whether this is the editor that other launches hand their files to.
+	pub single_instance: bool,
+	/// Problems with the arguments that don't stop the editor from starting,
+	/// like files that don't exist.
+	pub errors: Vec<String>,
+}
+
+/// Parses the command line arguments, returning an error for arguments the
+/// editor can't start with. Asking for help stops the parsing.
+pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Args, String> {
+	let mut args = Args::default();
+	let mut arguments = arguments.into_iter();
+
+	while let Some(argument) = arguments.next() {
+		match argument.as_str() {
+			"-h" | "--help" => {
+				args.help = true;
+
+				break;
+			}
+			"--new-window" => args.new_window = true,
+			"--readonly" => args.readonly = true,
+			"--theme" => {
+				let name = arguments.next().ok_or("--theme needs a theme name")?;
+
+				args.theme = Some(theme(&name).ok_or_else(|| format!("Unknown theme \"{name}\""))?);
+			}
+			"--config" => {
+				let path = PathBuf::from(arguments.next().ok_or("--config needs a path")?);
+
+				if path.parent().is_some_and(|parent| !parent.as_os_str().is_empty() && !parent.is_dir()) {
+					return Err(format!("The folder of the configuration \"{}\" doesn't exist", path.display()));
+				}
+
+				args.config = Some(path);
+			}
+			"--" => {
+				for argument in arguments.by_ref() {
+					args.add_file(&argument);
+				}
+			}
+			flag if flag.starts_with("--") => return Err(format!("Unknown option \"{flag}\"")),
+			_ => args.add_file(&argument),
+		}
+	}
+
+	Ok(args)
+}
+
+impl Args {
+	/// Whether nothing but files were given, which can then be opened in the
+	/// editor that is already running.
+	pub fn only_files(&self) -> bool {
+		!self.new_window && !self.readonly && self.theme.is_none() && self.config.is_none()
+	}
+
+	fn add_file(&mut self, argument: &str) {
+		let (path, position) = split_position(argument);
+
+		if path.is_file() {
+			// Recent files and the session shouldn't depend on the working folder
+			let path = std::path::absolute(&path).unwrap_or(path);
+
+			self.files.push(FileArgument { path, position });
+		} else if path.exists() {
+			self.errors.push(format!("\"{}\" is not a file", path.display()));
+		} else {
+			self.errors.push(format!("\"{}\" doesn't exist", path.display()));
+		}
+	}
+}
+
+/// Splits a `file:line:column` argument into the path and the zero-based
+/// cursor position. A file whose name really ends in such a suffix wins.
+fn split_position(argument: &str) -> (PathBuf, (usize, usize)) {
+	if Path::new(argument).exists() {
+		return (PathBuf::from(argument), (0, 0));
+	}
+
+	let number = |part: &str| part.parse::<usize>().ok().filter(|&number| number > 0);
+
+	let mut parts = argument.rsplitn(3, ':');
+	let last = parts.next().and_then(number);
+	let middle = parts.next();
+	let rest = parts.next();
+
+	match (rest, middle.and_then(number), last) {
+		(Some(path), Some(line), Some(column)) => (PathBuf::from(path), (line - 1, column - 1)),
+		(_, _, Some(line)) => {
+			let path = argument.rsplit_once(':').map_or(argument, |(path, _)| path);
+
+			(PathBuf::from(path), (line - 1, 0))
+		}
+		_ => (PathBuf::from(argument), (0, 0)),
+	}
+}
+
+/// Finds a theme by its configuration key or its name, ignoring case.
+fn theme(name: &str) -> Option<Theme> {
+	THEMES
+		.iter()
+		.find(|theme| {
+			config::theme_to_key(theme).eq_ignore_ascii_case(name)
+				|| theme.to_string().eq_ignore_ascii_case(name)
+		})
+		.cloned()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(arguments: &[&str]) -> Result<Args, String> {
+		super::parse(arguments.iter().map(ToString::to_string))
+	}
+
+	#[test]
+	fn splits_line_and_column() {
+		assert_eq!(split_position("notes.txt:12:5"), (PathBuf::from("notes.txt"), (11, 4)));
+		assert_eq!(split_position("notes.txt:12"), (PathBuf::from("notes.txt"), (11, 0)));
+		assert_eq!(split_position("notes.txt"), (PathBuf::from("notes.txt"), (0, 0)));
+	}
+
+	#[test]
+	fn keeps_suffixes_that_are_no_position() {
+		assert_eq!(split_position("notes.txt:0"), (PathBuf::from("notes.txt:0"), (0, 0)));
+		assert_eq!(split_position("notes.txt:a:3"), (PathBuf::from("notes.txt:a"), (2, 0)));
+		assert_eq!(split_position("C:\\notes.txt"), (PathBuf::from("C:\\notes.txt"), (0, 0)));
+	}
+
+	#[test]
+	fn counts_columns_in_characters() {
+		let argument = FileArgument {
+			path: PathBuf::from("notes.txt"),
+			position: (1, 2),
+		};
+
+		assert_eq!(argument.position_in("first\nééé\n"), (1, 4));
+		assert_eq!(argument.position_in("first\né\n"), (1, 2));
+	}
+
+	#[test]
+	fn help_is_left_to_the_caller() {
+		assert!(parse(&["--readonly", "--help", "--unknown"]).unwrap().help);
+	}
+
+	#[test]
+	fn rejects_unknown_options_and_themes() {
+		assert!(parse(&["--unknown"]).is_err());
+		assert!(parse(&["--theme", "no such theme"]).is_err());
+		assert!(parse(&["--theme"]).is_err());
+	}
+}
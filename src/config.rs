@@ -1,9 +1,17 @@
 use std::path::PathBuf;
-use std::sync::LazyLock;
+use std::sync::{LazyLock, OnceLock};
 use iced::{highlighter, Theme};
-use crate::{Editor, SettingsState};
+use crate::{keybindings, Editor, SettingsState};
+
+/// A configuration path given on the command line, which has to be set before
+/// [`CONFIG_PATH`] is first used.
+pub static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 pub static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+	if let Some(path) = CONFIG_OVERRIDE.get() {
+		return path.clone();
+	}
+
 	let config_path = dirs::config_dir().unwrap_or_default();
 
 	let editor_config_dir = config_path.join("multi_tab_text_editor");
@@ -117,8 +125,15 @@ pub fn key_to_syntax_theme(key: &str) -> highlighter::Theme {
 
 pub fn save(state: &Editor) {
 	let config = SettingsState {
-		theme: theme_to_key(&state.theme).to_string(),
+		theme: theme_to_key(&state.configured_theme).to_string(),
 		syntax_theme: syntax_theme_to_key(&state.highlighter_theme).to_string(),
+		history_limit: state.history_limit,
+		keybindings: keybindings::to_config(&state.keybindings),
+		recent_commands: state.palette.recent.clone(),
+		backup: state.backup,
+		recent_files: state.recent_files.clone(),
+		autosave: state.autosave,
+		autosave_delay: state.autosave_delay,
 	};
 
 	let config_path = &*CONFIG_PATH;
@@ -1,12 +1,30 @@
+use std::path::Path;
+
 use iced::{Alignment, Background, Border, Element, Length, Theme, theme};
 use iced::alignment::Horizontal;
 use iced::theme::Button;
-use iced::widget::{button, Column, ComboBox, container, row, text, tooltip};
+use iced::widget::{
+	button, checkbox, Column, ComboBox, container, horizontal_space, mouse_area, pane_grid, pick_list, row,
+	scrollable, text, text_input, tooltip, Row, Space,
+};
 use iced::widget::button::Appearance;
-use iced_aw::{card, quad, style};
+use iced_aw::menu::{Item, Menu, MenuBar};
+use iced_aw::{card, quad, style, ContextMenu};
 use iced_aw::widgets::InnerBounds;
 
-use crate::{Editor, Message};
+use crate::{CloseRequest, CloseScope, Editor, File, FileId, Message, SettingsPage};
+use crate::atomic_write::Backup;
+use crate::autosave;
+use crate::encoding::Encoding;
+use crate::find_in_files;
+use crate::history;
+use crate::palette;
+use crate::panes::Pane;
+use crate::quick_open;
+use crate::keybindings::{self, Action, Keybinding, Sequence};
+use crate::search;
+use crate::watcher::Conflict;
+use crate::workspace::{self, PromptKind, Workspace};
 use crate::editor::icons;
 
 pub fn separator(theme: &Theme) -> quad::Quad {
@@ -118,34 +136,399 @@ pub fn menu_button_disabled<'a>(
 	inner.into()
 }
 
-pub fn tab(
-	content: Element<Message>,
-	on_press: Message,
+/// The tabs of a pane, which scroll sideways once there are too many to fit,
+/// followed by a menu listing all of them.
+pub fn tab_strip<'a>(
+	state: &'a Editor,
+	pane: pane_grid::Pane,
+	tabs: &'a Pane,
+	active: usize,
+) -> Element<'a, Message> {
+	let strip = scrollable(
+		Row::from_vec(
+			tabs.tabs
+				.iter()
+				.map(|&index| {
+					let file = &state.files[index];
+
+					tab(
+						text(format!("{}{}", state.tab_name(index), if file.is_modified { "*" } else { "" }))
+							.width(Length::Fill)
+							.into(),
+						pane,
+						index,
+						active == index,
+						file.pinned,
+						file.path.as_deref(),
+					)
+				})
+				.collect(),
+		)
+			.spacing(5)
+	)
+		.id(tabs.tab_strip.clone())
+		.direction(scrollable::Direction::Horizontal(
+			scrollable::Properties::new().width(0).scroller_width(0),
+		))
+		.on_scroll(move |viewport| Message::TabsScrolled(pane, viewport))
+		.width(Length::Fill);
+
+	let overflows = tabs.tabs.len() > 1;
+
+	let all_tabs: Vec<Item<'_, Message, Theme, iced::Renderer>> = tabs.tabs
+		.iter()
+		.map(|&index| {
+			let file = &state.files[index];
+
+			Item::new(menu_button(
+				row![
+					if file.pinned { icons::pin_icon(12) } else { icons::file_icon(12) },
+					icon_text(&state.tab_name(index)),
+				]
+					.align_items(Alignment::Center),
+				Message::SelectTab(pane, index),
+			))
+		})
+		.collect();
+
+	let all_tabs = MenuBar::new(vec![Item::with_menu(
+		menubar_button(icons::list_icon(16), Some("Open Tabs"), Message::None),
+		Menu::new(all_tabs)
+			.max_width(180.0)
+			.offset(0.0)
+			.spacing(5.0)
+			.width(240.0),
+	)]);
+
+	row![]
+		.push_maybe(overflows.then(|| {
+			scroll_button(icons::chevron_left_icon(12), tabs.tab_scroll > 0.0, Message::ScrollTabs(pane, -1.0))
+		}))
+		.push(strip)
+		.push_maybe(overflows.then(|| {
+			scroll_button(icons::chevron_right_icon(12), tabs.tab_scroll < 1.0, Message::ScrollTabs(pane, 1.0))
+		}))
+		.push(all_tabs)
+		.push_maybe((state.panes.len() > 1).then(|| close_pane_button(pane)))
+		.width(Length::Fill)
+		.spacing(5)
+		.align_items(Alignment::Center)
+		.into()
+}
+
+fn scroll_button(icon: Element<'_, Message>, enabled: bool, action: Message) -> Element<'_, Message> {
+	button(icon)
+		.style(Button::Custom(Box::new(MenuButtonStyle)))
+		.padding([5, 2])
+		.on_press_maybe(enabled.then_some(action))
+		.into()
+}
+
+/// A tab, which is selected and starts getting dragged when pressed.
+fn tab<'a>(
+	content: Element<'a, Message>,
+	pane: pane_grid::Pane,
 	index: usize,
 	highlighted: bool,
-) -> Element<Message> {
-	button(
-		container(
-			row![
-					content,
-					button(icons::close_icon(16))
-						.style(Button::Custom(Box::new(MenuButtonStyle)))
-						.width(Length::Shrink)
-						.on_press(Message::CloseIndex(index))
-				]
-				.align_items(Alignment::Center)
+	pinned: bool,
+	path: Option<&Path>,
+) -> Element<'a, Message> {
+	// Pinned tabs have no close button, and clicking the pin unpins them
+	let action = if pinned {
+		button(icons::pin_icon(14)).on_press(Message::TogglePin(index))
+	} else {
+		button(icons::close_icon(16)).on_press(Message::CloseTab(pane, index))
+	};
+
+	let tab = button(
+		mouse_area(
+			container(
+				row![
+						content,
+						action
+							.style(Button::Custom(Box::new(MenuButtonStyle)))
+							.width(Length::Shrink)
+					]
+					.align_items(Alignment::Center)
+			)
+				.width(148)
+				.padding([5, 10])
+				.align_x(Horizontal::Center)
+				.center_y()
 		)
-			.width(128)
-			.align_x(Horizontal::Center)
-			.center_y()
+			.on_press(Message::StartTabDrag(pane, index))
+			.on_enter(Message::TabHovered(pane, index))
 	)
 		.style(if highlighted {
 			Button::Primary
 		} else {
 			Button::Custom(Box::new(MenuButtonStyle))
 		})
-		.on_press(on_press)
-		.padding([5, 10])
+		.on_press(Message::SelectTab(pane, index))
+		.padding(0);
+
+	let tab = ContextMenu::new(tab, move || tab_context_menu(pane, index, pinned));
+
+	match path {
+		Some(path) => tooltip(tab, text(path.display()), tooltip::Position::Bottom)
+			.style(theme::Container::Box)
+			.into(),
+		None => tab.into(),
+	}
+}
+
+pub fn close_pane_button<'a>(pane: pane_grid::Pane) -> Element<'a, Message> {
+	tooltip(
+		button(icons::close_icon(16))
+			.style(Button::Custom(Box::new(MenuButtonStyle)))
+			.on_press(Message::ClosePane(pane)),
+		"Close Pane",
+		tooltip::Position::Bottom,
+	)
+		.style(theme::Container::Box)
+		.into()
+}
+
+fn tab_context_menu<'a>(pane: pane_grid::Pane, index: usize, pinned: bool) -> Element<'a, Message> {
+	container(
+		Column::new()
+			.push(menu_button(text(if pinned { "Unpin" } else { "Pin" }), Message::TogglePin(index)))
+			.push(menu_button(text("Close"), Message::CloseIndex(index)))
+			.push(menu_button(text("Close Others"), Message::CloseMany(CloseScope::Others(index))))
+			.push(menu_button(
+				text("Close Tabs to the Right"),
+				Message::CloseMany(CloseScope::ToTheRight(pane, index)),
+			))
+			.push(menu_button(text("Close Saved"), Message::CloseMany(CloseScope::Saved)))
+			.push(menu_button(text("Close All"), Message::CloseMany(CloseScope::All)))
+			.spacing(2)
+	)
+		.width(180)
+		.padding(5)
+		.style(theme::Container::Box)
+		.into()
+}
+
+fn toggle_button<'a>(
+	label: &'a str,
+	tooltip_label: &'a str,
+	active: bool,
+	action: Message,
+) -> Element<'a, Message> {
+	tooltip(
+		button(text(label).font(*crate::JETBRAINS_MONO))
+			.style(if active {
+				Button::Primary
+			} else {
+				Button::Custom(Box::new(MenuButtonStyle))
+			})
+			.padding([4, 6])
+			.on_press(action),
+		tooltip_label,
+		tooltip::Position::Bottom,
+	)
+		.style(theme::Container::Box)
+		.into()
+}
+
+fn search_toggles<'a>(
+	options: search::Options,
+	on_change: fn(search::Options) -> Message,
+) -> Element<'a, Message> {
+	row![
+		toggle_button(
+			"Aa",
+			"Match case",
+			options.case_sensitive,
+			on_change(search::Options {
+				case_sensitive: !options.case_sensitive,
+				..options
+			}),
+		),
+		toggle_button(
+			"ab",
+			"Match whole word",
+			options.whole_word,
+			on_change(search::Options {
+				whole_word: !options.whole_word,
+				..options
+			}),
+		),
+		toggle_button(
+			".*",
+			"Use regular expression",
+			options.regex,
+			on_change(search::Options {
+				regex: !options.regex,
+				..options
+			}),
+		),
+	]
+		.spacing(5)
+		.into()
+}
+
+pub fn find_bar<'a>(state: &'a Editor, error: Option<&regex::Error>) -> Element<'a, Message> {
+	let search = &state.search;
+
+	let find_row = row![
+		text_input("Find", &search.query)
+			.id(search::QUERY_ID.clone())
+			.on_input(Message::SearchQueryChanged)
+			.on_submit(Message::FindNext)
+			.font(*crate::JETBRAINS_MONO),
+		search_toggles(search.options, Message::SetSearchOptions),
+		menubar_button(icons::arrow_up_icon(14), Some("Previous match"), Message::FindPrevious),
+		menubar_button(icons::arrow_down_icon(14), Some("Next match"), Message::FindNext),
+		menubar_button(icons::close_icon(14), Some("Close"), Message::HideSearch),
+	]
+		.spacing(5)
+		.align_items(Alignment::Center);
+
+	let replace_row = row![
+		text_input("Replace", &search.replacement)
+			.on_input(Message::ReplacementChanged)
+			.on_submit(Message::ReplaceOne)
+			.font(*crate::JETBRAINS_MONO),
+		button("Replace")
+			.style(Button::Secondary)
+			.on_press(Message::ReplaceOne),
+		button("Replace All")
+			.style(Button::Secondary)
+			.on_press(Message::ReplaceAll),
+	]
+		.spacing(5)
+		.align_items(Alignment::Center);
+
+	let mut bar = Column::new()
+		.push(find_row)
+		.push(replace_row)
+		.spacing(5);
+
+	if let Some(error) = error {
+		let color = state.theme.extended_palette().danger.base.color;
+
+		bar = bar.push(
+			text(error.to_string().lines().last().unwrap_or("Invalid regular expression").to_string())
+				.style(color)
+		);
+	}
+
+	bar.into()
+}
+
+pub fn find_in_files_panel(state: &Editor) -> Element<'_, Message> {
+	let panel = &state.find_in_files;
+	let mono = *crate::JETBRAINS_MONO;
+
+	let directory = panel.directory
+		.as_ref()
+		.and_then(|directory| directory.file_name())
+		.map_or_else(|| "Choose a folder".to_string(), |name| name.to_string_lossy().to_string());
+
+	let search_row = row![
+		text_input("Find in files", &panel.search.query)
+			.on_input(Message::FindInFilesQueryChanged)
+			.on_submit(Message::RunFindInFiles)
+			.font(mono),
+		search_toggles(panel.search.options, Message::SetFindInFilesOptions),
+		pick_list(
+			find_in_files::Scope::ALL.as_slice(),
+			Some(panel.scope),
+			Message::SetFindInFilesScope,
+		),
+		button("Search")
+			.style(Button::Primary)
+			.on_press_maybe((!panel.is_searching).then_some(Message::RunFindInFiles)),
+		menubar_button(icons::close_icon(14), Some("Close"), Message::HideFindInFiles),
+	]
+		.spacing(5)
+		.align_items(Alignment::Center);
+
+	let mut controls = Column::new()
+		.push(search_row)
+		.spacing(5);
+
+	if panel.scope == find_in_files::Scope::Directory {
+		controls = controls.push(
+			row![
+				button(row![icons::folder_icon(13), text(format!("  {directory}"))].align_items(Alignment::Center))
+					.style(Button::Secondary)
+					.on_press(Message::PickSearchDirectory),
+				text_input("Files to include, e.g. *.rs, src/**", &panel.include)
+					.on_input(Message::IncludeChanged)
+					.on_submit(Message::RunFindInFiles),
+				text_input("Files to exclude, e.g. target/**", &panel.exclude)
+					.on_input(Message::ExcludeChanged)
+					.on_submit(Message::RunFindInFiles),
+			]
+				.spacing(5)
+				.align_items(Alignment::Center)
+		);
+	}
+
+	controls = controls.push(
+		row![
+			text_input("Replace", &panel.search.replacement)
+				.on_input(Message::FindInFilesReplacementChanged)
+				.font(mono),
+			button("Replace All")
+				.style(Button::Secondary)
+				.on_press_maybe((!panel.results.is_empty()).then_some(Message::ReplaceInFiles)),
+		]
+			.spacing(5)
+			.align_items(Alignment::Center)
+	);
+
+	let status = if panel.is_searching {
+		"Searching...".to_string()
+	} else if let Some(status) = &panel.status {
+		status.clone()
+	} else {
+		format!("{} matches in {} files", panel.match_count(), panel.results.len())
+	};
+
+	let mut results = Column::new().spacing(2);
+
+	for file in &panel.results {
+		results = results.push(
+			text(format!("{} ({})", file.name, file.matches.len()))
+				.style(state.theme.extended_palette().primary.strong.color)
+		);
+
+		for found in &file.matches {
+			results = results.push(menu_button(
+				row![
+					text(format!("{:>6}  ", found.line + 1)).font(mono),
+					text(&found.preview).font(mono),
+				],
+				Message::OpenSearchResult(file.location.clone(), (found.line, found.column)),
+			));
+		}
+	}
+
+	Column::new()
+		.push(separator(&state.theme))
+		.push(controls)
+		.push(text(status))
+		.push(scrollable(results).height(200))
+		.spacing(5)
+		.into()
+}
+
+pub fn menu_item<'a>(
+	icon: Element<'a, Message>,
+	label: &str,
+	shortcut: Option<String>,
+) -> Element<'a, Message> {
+	row![
+		icon,
+		icon_text(label),
+		horizontal_space(),
+		text(shortcut.unwrap_or_default()).size(11),
+	]
+		.width(Length::Fill)
+		.align_items(Alignment::Center)
 		.into()
 }
 
@@ -187,7 +570,22 @@ pub fn about_modal<'a>(theme: &Theme) -> Element<'a, Message> {
 		.into()
 }
 
-pub fn settings_modal(state: &Editor) -> Element<Message> {
+pub fn settings_modal(state: &Editor) -> Element<'_, Message> {
+	let page_button = |label, page| {
+		button(text(label))
+			.style(if state.settings_page == page {
+				Button::Primary
+			} else {
+				Button::Secondary
+			})
+			.on_press(Message::ShowSettingsPage(page))
+	};
+
+	let page = match state.settings_page {
+		SettingsPage::General => general_settings(state),
+		SettingsPage::Keybindings => keybinding_settings(state),
+	};
+
 	card(
 		row![
 			text("Settings")
@@ -199,26 +597,1002 @@ pub fn settings_modal(state: &Editor) -> Element<Message> {
 				.on_press(Message::HideModal)
 		].align_items(Alignment::Center),
 		Column::new()
-			.push(text("Selected theme"))
-			.push(ComboBox::new(
-				&state.themes,
-				"Select a theme",
-				Some(&state.theme),
-				Message::SelectTheme
-			))
+			.push(
+				row![
+					page_button("General", SettingsPage::General),
+					page_button("Keybindings", SettingsPage::Keybindings),
+				]
+					.spacing(5)
+			)
 			.push(separator(&state.theme))
-			.push(text("Selected syntax highlighting theme"))
-			.push(ComboBox::new(
-				&state.highlighter_themes,
-				"Select a highlighting theme",
-				Some(&state.highlighter_theme),
-				Message::SelectSyntaxTheme
-			))
+			.push(page)
+			.width(600)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(640)
+		.height(420)
+		.into()
+}
+
+fn general_settings(state: &Editor) -> Element<'_, Message> {
+	Column::new()
+		.push(text("Selected theme"))
+		.push(ComboBox::new(
+			&state.themes,
+			"Select a theme",
+			Some(&state.theme),
+			Message::SelectTheme
+		))
+		.push(separator(&state.theme))
+		.push(text("Selected syntax highlighting theme"))
+		.push(ComboBox::new(
+			&state.highlighter_themes,
+			"Select a highlighting theme",
+			Some(&state.highlighter_theme),
+			Message::SelectSyntaxTheme
+		))
+		.push(separator(&state.theme))
+		.push(text("Undo history limit"))
+		.push(pick_list(
+			history::LIMITS.as_slice(),
+			Some(state.history_limit),
+			Message::SetHistoryLimit
+		))
+		.push(separator(&state.theme))
+		.push(text("Backup copies when saving"))
+		.push(pick_list(
+			Backup::ALL.as_slice(),
+			Some(state.backup),
+			Message::SetBackup
+		))
+		.push(separator(&state.theme))
+		.push(text("Autosave"))
+		.push(
+			row![
+				pick_list(
+					autosave::Mode::ALL.as_slice(),
+					Some(state.autosave),
+					Message::SetAutosave
+				),
+				text("after"),
+				pick_list(
+					autosave::DELAYS.as_slice(),
+					Some(state.autosave_delay),
+					Message::SetAutosaveDelay
+				),
+				text("seconds without edits"),
+			]
+				.spacing(5)
+				.align_items(Alignment::Center)
+		)
+		.push(separator(&state.theme))
+		.into()
+}
+
+fn keybinding_settings(state: &Editor) -> Element<'_, Message> {
+	let palette = state.theme.extended_palette();
+
+	let mut errors = Column::new().spacing(2);
+
+	for error in &state.keybinding_errors {
+		errors = errors.push(
+			text(format!("Invalid keybinding: {error}"))
+				.style(palette.danger.strong.color)
+		);
+	}
+
+	let mut bindings = Column::new().spacing(5);
+
+	for action in Action::ALL {
+		let bound: Vec<&Keybinding> = state.keybindings
+			.iter()
+			.filter(|binding| binding.action == action)
+			.collect();
+
+		let sequences = if bound.is_empty() {
+			"Unbound".to_string()
+		} else {
+			bound
+				.iter()
+				.map(|binding| binding.sequence.to_string())
+				.collect::<Vec<_>>()
+				.join(", ")
+		};
+
+		let mut conflicts: Vec<Action> = Vec::new();
+
+		for conflict in bound
+			.iter()
+			.flat_map(|binding| keybindings::conflicts(&state.keybindings, binding))
+		{
+			if !conflicts.contains(&conflict) {
+				conflicts.push(conflict);
+			}
+		}
+
+		let controls = match &state.recording {
+			Some(recording) if recording.action == action => row![
+				text(if recording.chords.is_empty() {
+					"Press the new keys...".to_string()
+				} else {
+					Sequence(recording.chords.clone()).to_string()
+				})
+					.style(palette.primary.strong.color),
+				button("Apply")
+					.style(Button::Primary)
+					.on_press_maybe((!recording.chords.is_empty()).then_some(Message::ApplyRecording)),
+				button("Cancel")
+					.style(Button::Secondary)
+					.on_press(Message::CancelRecording),
+			],
+			_ => row![
+				text(sequences),
+				button("Rebind")
+					.style(Button::Secondary)
+					.on_press(Message::StartRecording(action)),
+				button("Unbind")
+					.style(Button::Secondary)
+					.on_press_maybe((!bound.is_empty()).then_some(Message::Unbind(action))),
+			],
+		};
+
+		let mut entry = Column::new()
+			.push(
+				row![
+					text(action.to_string()),
+					horizontal_space(),
+					controls.spacing(5).align_items(Alignment::Center),
+				]
+					.align_items(Alignment::Center)
+			);
+
+		if !conflicts.is_empty() {
+			entry = entry.push(
+				text(format!(
+					"Conflicts with {}",
+					conflicts
+						.iter()
+						.map(ToString::to_string)
+						.collect::<Vec<_>>()
+						.join(", ")
+				))
+					.size(11)
+					.style(palette.danger.strong.color)
+			);
+		}
+
+		bindings = bindings.push(entry);
+	}
+
+	Column::new()
+		.push(errors)
+		.push(scrollable(bindings.padding([0, 15, 0, 0])).height(240))
+		.push(separator(&state.theme))
+		.push(
+			row![
+				horizontal_space(),
+				button("Reset to Defaults")
+					.style(Button::Destructive)
+					.on_press(Message::ResetKeybindings),
+			]
+		)
+		.spacing(5)
+		.into()
+}
+
+pub fn encoding_modal(state: &Editor) -> Element<'_, Message> {
+	let file = &state.files[state.current];
+
+	let mut encodings = Column::new().spacing(5);
+
+	for encoding in Encoding::ALL {
+		let label = if encoding == file.format.encoding {
+			text(format!("{encoding} (current)"))
+		} else {
+			text(encoding.to_string())
+		};
+
+		encodings = encodings.push(
+			row![
+				label,
+				horizontal_space(),
+				button("Reopen")
+					.style(Button::Secondary)
+					.on_press_maybe(file.path.as_ref().map(|_| Message::ReopenWithEncoding(encoding))),
+				button("Save")
+					.style(Button::Secondary)
+					.on_press(Message::SaveWithEncoding(encoding)),
+			]
+				.spacing(5)
+				.align_items(Alignment::Center),
+		);
+	}
+
+	card(
+		row![
+			text("Encoding")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(encodings)
 			.push(separator(&state.theme))
+			.push(
+				checkbox("Write a byte order mark", file.format.bom)
+					.on_toggle_maybe(file.format.encoding.is_unicode().then_some(Message::SetBom))
+			)
 			.width(600)
 	)
 		.style(style::card::CardStyles::Dark)
 		.width(640)
-		.height(360)
+		.into()
+}
+
+/// Asks what to do with the unsaved changes of a tab before `save` or
+/// `discard` goes on with closing or reopening it.
+pub fn unsaved_changes_modal(state: &Editor, id: FileId, save: Message, discard: Message) -> Element<'_, Message> {
+	card(
+		row![
+			text("Unsaved changes")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text(format!(
+				"Do you want to save the changes made to {}?",
+				state.file(id).map_or("New file", File::name)
+			)))
+			.push(text("Your changes will be lost if you don't save them."))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Save")
+						.style(Button::Primary)
+						.on_press(save),
+					button("Don't Save")
+						.style(Button::Secondary)
+						.on_press(discard),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn unsaved_changes_many_modal<'a>(
+	state: &'a Editor,
+	request: &CloseRequest,
+) -> Element<'a, Message> {
+	let mut files = Column::new().spacing(2);
+
+	for index in state.close_request_indices(request) {
+		if state.files[index].is_modified {
+			files = files.push(text(format!("  \u{2022}  {}", state.files[index].name())));
+		}
+	}
+
+	card(
+		row![
+			text("Unsaved changes")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text("The following files have unsaved changes:"))
+			.push(files)
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Save All")
+						.style(Button::Primary)
+						.on_press(Message::SaveAllAndClose(request.clone())),
+					button("Discard All")
+						.style(Button::Destructive)
+						.on_press(Message::DiscardAllAndClose(request.clone())),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn command_palette(state: &Editor) -> Element<'_, Message> {
+	let entries = palette::filter(
+		palette::entries(state),
+		&state.palette.query,
+		&state.palette.recent,
+	);
+
+	// Keeps the selected command in view while moving through the list
+	let first = state.palette.selected.saturating_sub(palette::MAX_VISIBLE - 1);
+
+	let mut list = Column::new().spacing(2);
+
+	for (index, entry) in entries.iter().enumerate().skip(first).take(palette::MAX_VISIBLE) {
+		let mut label = row![text(&entry.label), horizontal_space()]
+			.align_items(Alignment::Center);
+
+		if state.palette.recent.contains(&entry.label) {
+			label = label.push(text("recently used").size(11));
+		}
+
+		if let Some(shortcut) = &entry.shortcut {
+			label = label.push(text(shortcut).size(11));
+		}
+
+		list = list.push(
+			button(container(label.spacing(10)).padding([2, 4]))
+				.width(Length::Fill)
+				.style(if index == state.palette.selected {
+					Button::Primary
+				} else {
+					Button::Custom(Box::new(MenuButtonStyle))
+				})
+				.on_press(Message::RunCommand(index))
+		);
+	}
+
+	if entries.is_empty() {
+		list = list.push(text("No matching commands"));
+	}
+
+	container(
+		Column::new()
+			.push(
+				text_input("Type a command", &state.palette.query)
+					.id(palette::INPUT_ID.clone())
+					.on_input(Message::PaletteQueryChanged)
+					.on_submit(Message::RunCommand(state.palette.selected))
+					.padding(8)
+			)
+			.push(list)
+			.spacing(10)
+	)
+		.width(560)
+		.padding(10)
+		.style(theme::Container::Box)
+		.into()
+}
+
+pub fn quick_open(state: &Editor) -> Element<'_, Message> {
+	let matches = &state.quick_open.matches;
+
+	// Keeps the selected file in view while moving through the list
+	let first = state.quick_open.selected.saturating_sub(palette::MAX_VISIBLE - 1);
+
+	let mut list = Column::new().spacing(2);
+
+	for (index, found) in matches.iter().enumerate().skip(first).take(palette::MAX_VISIBLE) {
+		let mut label = row![text(&found.label), horizontal_space()]
+			.align_items(Alignment::Center);
+
+		if found.recent {
+			label = label.push(text("recently opened").size(11));
+		}
+
+		list = list.push(
+			button(container(label.spacing(10)).padding([2, 4]))
+				.width(Length::Fill)
+				.style(if index == state.quick_open.selected {
+					Button::Primary
+				} else {
+					Button::Custom(Box::new(MenuButtonStyle))
+				})
+				.on_press(Message::OpenQuickOpenEntry(found.path.clone()))
+		);
+	}
+
+	if matches.is_empty() {
+		list = list.push(text(match &state.workspace {
+			Some(workspace) if workspace.is_indexing && workspace.files.is_empty() => "Indexing files...",
+			Some(_) => "No matching files",
+			None => "No matching recent files. Open a folder to search all of its files",
+		}));
+	}
+
+	let mut input = text_input("Go to file", &state.quick_open.query)
+		.id(quick_open::INPUT_ID.clone())
+		.on_input(Message::QuickOpenQueryChanged)
+		.padding(8);
+
+	if let Some(found) = matches.get(state.quick_open.selected) {
+		input = input.on_submit(Message::OpenQuickOpenEntry(found.path.clone()));
+	}
+
+	container(
+		Column::new()
+			.push(input)
+			.push(list)
+			.spacing(10)
+	)
+		.width(560)
+		.padding(10)
+		.style(theme::Container::Box)
+		.into()
+}
+
+/// The tabs to switch to, the most recently used first.
+pub fn tab_switcher(state: &Editor) -> Element<'_, Message> {
+	let switcher = &state.tab_switcher;
+
+	// Keeps the selected tab in view while cycling through the list
+	let first = switcher.selected.saturating_sub(palette::MAX_VISIBLE - 1);
+
+	let mut list = Column::new().spacing(2);
+
+	for (position, &index) in switcher.tabs.iter().enumerate().skip(first).take(palette::MAX_VISIBLE) {
+		let Some(file) = state.files.get(index) else {
+			continue;
+		};
+
+		let folder = file.path
+			.as_deref()
+			.and_then(Path::parent)
+			.map(|folder| folder.display().to_string())
+			.unwrap_or_default();
+
+		list = list.push(
+			button(
+				container(
+					row![
+						text(format!("{}{}", state.tab_name(index), if file.is_modified { "*" } else { "" })),
+						horizontal_space(),
+						text(folder).size(11),
+					]
+						.align_items(Alignment::Center)
+						.spacing(10)
+				)
+					.padding([2, 4])
+			)
+				.width(Length::Fill)
+				.style(if position == switcher.selected {
+					Button::Primary
+				} else {
+					Button::Custom(Box::new(MenuButtonStyle))
+				})
+				.on_press(Message::SwitchToTab(index))
+		);
+	}
+
+	container(list)
+		.width(560)
+		.padding(10)
+		.style(theme::Container::Box)
+		.into()
+}
+
+/// Lines of a comparison beyond this many aren't shown, to keep it responsive.
+const MAX_DIFF_LINES: usize = 2000;
+
+pub fn conflict_banner<'a>(index: usize, conflict: &Conflict) -> Element<'a, Message> {
+	let content = match conflict {
+		Conflict::Changed { .. } => row![
+			text("This file was changed by another program."),
+			horizontal_space(),
+			button("Reload")
+				.style(Button::Primary)
+				.on_press(Message::ReloadFromDisk(index)),
+			button("Keep Mine")
+				.style(Button::Secondary)
+				.on_press(Message::KeepMine(index)),
+			button("Compare")
+				.style(Button::Secondary)
+				.on_press(Message::ShowModal(crate::ModalType::Compare(index))),
+		],
+		Conflict::Deleted => row![
+			text("This file was deleted by another program."),
+			horizontal_space(),
+			button("Keep Mine")
+				.style(Button::Primary)
+				.on_press(Message::KeepMine(index)),
+			button("Close")
+				.style(Button::Secondary)
+				.on_press(Message::CloseIndex(index)),
+		],
+	};
+
+	container(
+		content
+			.spacing(5)
+			.align_items(Alignment::Center)
+	)
+		.width(Length::Fill)
+		.padding(5)
+		.style(theme::Container::Box)
+		.into()
+}
+
+pub fn changed_on_disk_modal<'a>(
+	state: &'a Editor,
+	id: FileId,
+	request: Option<&CloseRequest>,
+) -> Element<'a, Message> {
+	card(
+		row![
+			text("File changed on disk")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text(format!(
+				"\"{}\" was changed by another program since it was opened. Do you want to overwrite those changes?",
+				state.file(id).map_or("New file", File::name)
+			)))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Overwrite")
+						.style(Button::Destructive)
+						.on_press(Message::Overwrite(id, request.cloned())),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn compare_modal(state: &Editor, index: usize) -> Element<'_, Message> {
+	let palette = state.theme.extended_palette();
+	let file = &state.files[index];
+
+	let mut lines = Column::new();
+
+	if let Some(Conflict::Changed { text: on_disk, .. }) = &file.conflict {
+		let mine = crate::buffer_text(&file.content);
+		let diff = similar::TextDiff::from_lines(on_disk.as_str(), mine.as_str());
+		let groups = diff.grouped_ops(3);
+
+		let changes = groups
+			.iter()
+			.enumerate()
+			.flat_map(|(group, operations)| {
+				// Separates the groups of changes, which are far apart
+				let gap = (group > 0).then(|| ("", "...".to_string()));
+
+				gap.into_iter().chain(
+					operations
+						.iter()
+						.flat_map(|operation| diff.iter_changes(operation))
+						.map(|change| {
+							let sign = match change.tag() {
+								similar::ChangeTag::Delete => "-",
+								similar::ChangeTag::Insert => "+",
+								similar::ChangeTag::Equal => " ",
+							};
+
+							(sign, change.value().trim_end_matches('\n').to_string())
+						})
+				)
+			})
+			.take(MAX_DIFF_LINES);
+
+		for (sign, line) in changes {
+			let line = text(format!("{sign} {line}"))
+				.font(*crate::JETBRAINS_MONO)
+				.size(12);
+
+			lines = lines.push(match sign {
+				"-" => line.style(palette.danger.strong.color),
+				"+" => line.style(palette.success.strong.color),
+				_ => line,
+			});
+		}
+	}
+
+	card(
+		row![
+			text("Compare with disk")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text("Lines marked - are only on disk, lines marked + are only in the editor."))
+			.push(scrollable(lines.padding([0, 15, 0, 0])).height(360))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Reload")
+						.style(Button::Primary)
+						.on_press(Message::ReloadFromDisk(index)),
+					button("Keep Mine")
+						.style(Button::Secondary)
+						.on_press(Message::KeepMine(index)),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(720)
+		.into()
+}
+
+pub fn recovery_modal(state: &Editor) -> Element<'_, Message> {
+	let mut files = Column::new().spacing(2);
+
+	for swap in &state.leftovers.swaps {
+		files = files.push(text(format!(
+			"  \u{2022}  {}",
+			swap.path
+				.as_deref()
+				.map_or_else(|| "New file".to_string(), |path| path.display().to_string())
+		)));
+	}
+
+	card(
+		row![
+			text("Recover unsaved changes")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text("The editor didn't close properly last time. The following files had unsaved changes:"))
+			.push(scrollable(files).height(Length::Shrink))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Recover")
+						.style(Button::Primary)
+						.on_press(Message::RecoverSwaps),
+					button("Discard")
+						.style(Button::Destructive)
+						.on_press(Message::DiscardSwaps),
+					button("Later")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+fn sidebar_button<'a>(
+	icon: Element<'a, Message>,
+	tooltip_label: &'a str,
+	action: Message,
+) -> Element<'a, Message> {
+	tooltip(
+		button(icon)
+			.style(Button::Custom(Box::new(MenuButtonStyle)))
+			.padding([2, 4])
+			.on_press(action),
+		tooltip_label,
+		tooltip::Position::Bottom,
+	)
+		.style(theme::Container::Box)
+		.into()
+}
+
+pub fn sidebar(state: &Editor) -> Element<'_, Message> {
+	let content: Element<'_, Message> = if let Some(workspace) = &state.workspace {
+		let target = workspace.target_directory();
+
+		let header = row![
+			text(
+				workspace.root
+					.file_name()
+					.map_or_else(|| workspace.root.display().to_string(), |name| name.to_string_lossy().to_string())
+			)
+				.width(Length::Fill),
+			sidebar_button(icons::new_icon(12), "New File", Message::ShowPrompt(PromptKind::NewFile(target.clone()))),
+			sidebar_button(icons::new_folder_icon(12), "New Folder", Message::ShowPrompt(PromptKind::NewFolder(target))),
+			sidebar_button(icons::refresh_icon(12), "Refresh", Message::RefreshWorkspace),
+			sidebar_button(icons::close_icon(12), "Close Folder", Message::CloseFolder),
+		]
+			.align_items(Alignment::Center);
+
+		let prompt = workspace.prompt.as_ref().map(|prompt| {
+			let placeholder = match prompt.kind {
+				PromptKind::NewFile(_) => "Name of the new file",
+				PromptKind::NewFolder(_) => "Name of the new folder",
+				PromptKind::Rename(_) => "New name",
+			};
+
+			text_input(placeholder, &prompt.name)
+				.id(workspace::PROMPT_ID.clone())
+				.on_input(Message::PromptChanged)
+				.on_submit(Message::SubmitPrompt)
+				.padding(4)
+		});
+
+		let mut tree = Column::new().spacing(1);
+
+		for (depth, entry) in workspace.visible() {
+			tree = tree.push(tree_entry(workspace, depth, entry));
+		}
+
+		Column::new()
+			.push(header)
+			.push_maybe(prompt)
+			.push(scrollable(tree).height(Length::Fill))
+			.spacing(5)
+			.into()
+	} else {
+		Column::new()
+			.push(text("No folder is open"))
+			.push(
+				button("Open Folder")
+					.style(Button::Primary)
+					.on_press(Message::OpenFolder)
+			)
+			.spacing(10)
+			.into()
+	};
+
+	container(content)
+		.width(workspace::SIDEBAR_WIDTH)
+		.height(Length::Fill)
+		.padding(5)
+		.style(theme::Container::Box)
+		.into()
+}
+
+fn tree_entry<'a>(workspace: &Workspace, depth: usize, entry: &'a workspace::Entry) -> Element<'a, Message> {
+	let icon = if !entry.is_directory {
+		icons::file_icon(12)
+	} else if workspace.expanded.contains(&entry.path) {
+		icons::chevron_down_icon(12)
+	} else {
+		icons::chevron_right_icon(12)
+	};
+
+	#[allow(clippy::cast_precision_loss)]
+	let indent = Space::with_width(depth as f32 * 12.0);
+
+	let label = button(
+		row![indent, icon, text(&entry.name)]
+			.spacing(5)
+			.align_items(Alignment::Center)
+	)
+		.width(Length::Fill)
+		.padding([2, 4])
+		.style(if workspace.selected.as_ref() == Some(&entry.path) {
+			Button::Primary
+		} else {
+			Button::Custom(Box::new(MenuButtonStyle))
+		})
+		.on_press(if entry.is_directory {
+			Message::ToggleDirectory(entry.path.clone())
+		} else {
+			Message::OpenEntry(entry.path.clone())
+		});
+
+	let path = entry.path.clone();
+	let is_directory = entry.is_directory;
+
+	ContextMenu::new(label, move || tree_context_menu(&path, is_directory)).into()
+}
+
+fn tree_context_menu<'a>(path: &Path, is_directory: bool) -> Element<'a, Message> {
+	let directory = if is_directory {
+		path.to_path_buf()
+	} else {
+		path.parent().map(Path::to_path_buf).unwrap_or_default()
+	};
+
+	container(
+		Column::new()
+			.push(menu_button(text("New File"), Message::ShowPrompt(PromptKind::NewFile(directory.clone()))))
+			.push(menu_button(text("New Folder"), Message::ShowPrompt(PromptKind::NewFolder(directory.clone()))))
+			.push(menu_button(text("Rename"), Message::ShowPrompt(PromptKind::Rename(path.to_path_buf()))))
+			.push(menu_button(
+				text("Delete"),
+				Message::ShowModal(crate::ModalType::ConfirmDelete(path.to_path_buf(), is_directory)),
+			))
+			.push(menu_button(text("Reveal in File Manager"), Message::ShowInExplorer(directory)))
+			.spacing(2)
+	)
+		.width(180)
+		.padding(5)
+		.style(theme::Container::Box)
+		.into()
+}
+
+pub fn confirm_delete_modal<'a>(state: &'a Editor, path: &Path, is_directory: bool) -> Element<'a, Message> {
+	let name = path
+		.file_name()
+		.map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string());
+
+	card(
+		row![
+			text("Delete")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text(if is_directory {
+				format!("Do you want to delete the folder \"{name}\" and everything in it?")
+			} else {
+				format!("Do you want to delete \"{name}\"?")
+			}))
+			.push(text("This can't be undone."))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Delete")
+						.style(Button::Destructive)
+						.on_press(Message::ApplyOperation(workspace::Operation::Delete(path.to_path_buf()))),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn confirm_replace_in_files_modal(state: &Editor, count: usize) -> Element<'_, Message> {
+	card(
+		row![
+			text("Replace in files")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text(format!(
+				"Do you want to replace the matches in {count} file{} that aren't open?",
+				if count == 1 { "" } else { "s" }
+			)))
+			.push(text("The files are changed on disk, which can't be undone."))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Replace")
+						.style(Button::Destructive)
+						.on_press(Message::ApplyReplaceInFiles),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn deleted_files_modal<'a>(state: &'a Editor, ids: &[FileId]) -> Element<'a, Message> {
+	let mut files = Column::new().spacing(2);
+
+	for file in ids.iter().filter_map(|&id| state.file(id)) {
+		files = files.push(text(format!("  \u{2022}  {}", file.name())));
+	}
+
+	card(
+		row![
+			text("Deleted files")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text("The following files are still open:"))
+			.push(files)
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Close Tabs")
+						.style(Button::Primary)
+						.on_press(Message::CloseTabs(ids.to_vec())),
+					button("Keep Open")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
+		.into()
+}
+
+pub fn dropped_folder_modal<'a>(state: &'a Editor, path: &Path) -> Element<'a, Message> {
+	card(
+		row![
+			text("Open folder")
+				.width(Length::Fill)
+				.size(24),
+			button(icons::close_icon(16))
+				.style(Button::Custom(Box::new(MenuButtonStyle)))
+				.width(Length::Shrink)
+				.on_press(Message::HideModal)
+		].align_items(Alignment::Center),
+		Column::new()
+			.push(text(format!(
+				"Do you want to open \"{}\" as a folder in the sidebar, or open every file in it as a tab?",
+				path.display()
+			)))
+			.push(separator(&state.theme))
+			.push(
+				row![
+					horizontal_space(),
+					button("Open Folder")
+						.style(Button::Primary)
+						.on_press(Message::OpenWorkspace(path.to_path_buf())),
+					button("Open All Files")
+						.style(Button::Secondary)
+						.on_press(Message::OpenFilesIn(path.to_path_buf())),
+					button("Cancel")
+						.style(Button::Secondary)
+						.on_press(Message::HideModal),
+				]
+					.spacing(5)
+			)
+			.spacing(5)
+	)
+		.style(style::card::CardStyles::Dark)
+		.width(480)
 		.into()
 }
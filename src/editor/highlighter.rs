@@ -0,0 +1,106 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use iced::advanced::text::highlighter::{self, Format};
+use iced::{Font, Theme};
+
+/// A syntax highlighter that also highlights search matches on top of the syntax.
+pub struct Highlighter {
+	syntax: iced::highlighter::Highlighter,
+	matches: Arc<Vec<LineMatch>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+	pub syntax: iced::highlighter::Settings,
+	/// The matches to highlight, sorted by position.
+	pub matches: Arc<Vec<LineMatch>>,
+}
+
+/// The part of a search match that lies on a single line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMatch {
+	pub line: usize,
+	pub columns: Range<usize>,
+	/// Whether this is part of the match the cursor is on.
+	pub current: bool,
+}
+
+pub enum Highlight {
+	Syntax(iced::highlighter::Highlight),
+	Match,
+	CurrentMatch,
+}
+
+impl Highlight {
+	pub fn to_format(&self, theme: &Theme) -> Format<Font> {
+		let palette = theme.extended_palette();
+
+		match self {
+			Highlight::Syntax(highlight) => highlight.to_format(),
+			Highlight::Match => Format {
+				color: Some(palette.primary.strong.color),
+				font: None,
+			},
+			Highlight::CurrentMatch => Format {
+				color: Some(palette.danger.strong.color),
+				font: None,
+			},
+		}
+	}
+}
+
+impl highlighter::Highlighter for Highlighter {
+	type Settings = Settings;
+	type Highlight = Highlight;
+
+	type Iterator<'a> = Box<dyn Iterator<Item = (Range<usize>, Self::Highlight)> + 'a>;
+
+	fn new(settings: &Self::Settings) -> Self {
+		Highlighter {
+			syntax: iced::highlighter::Highlighter::new(&settings.syntax),
+			matches: settings.matches.clone(),
+		}
+	}
+
+	fn update(&mut self, new_settings: &Self::Settings) {
+		self.syntax.update(&new_settings.syntax);
+		self.matches = new_settings.matches.clone();
+	}
+
+	fn change_line(&mut self, line: usize) {
+		self.syntax.change_line(line);
+	}
+
+	fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+		let index = self.syntax.current_line();
+
+		let start = self.matches.partition_point(|found| found.line < index);
+		let end = self.matches.partition_point(|found| found.line <= index);
+
+		// Later spans take precedence, so matches are laid over the syntax highlighting
+		let matches = self.matches[start..end]
+			.iter()
+			.map(|found| {
+				let highlight = if found.current {
+					Highlight::CurrentMatch
+				} else {
+					Highlight::Match
+				};
+
+				(found.columns.clone(), highlight)
+			})
+			.collect::<Vec<_>>();
+
+		Box::new(
+			self.syntax
+				.highlight_line(line)
+				.map(|(range, highlight)| (range, Highlight::Syntax(highlight)))
+				.chain(matches)
+		)
+	}
+
+	fn current_line(&self) -> usize {
+		self.syntax.current_line()
+	}
+}
@@ -43,6 +43,86 @@ pub fn settings_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
 	icon('\u{F3E5}', size)
 }
 
+pub fn encoding_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F68A}', size)
+}
+
+pub fn undo_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F117}', size)
+}
+
+pub fn redo_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F116}', size)
+}
+
+pub fn search_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F52A}', size)
+}
+
+pub fn arrow_up_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F148}', size)
+}
+
+pub fn arrow_down_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F128}', size)
+}
+
+pub fn files_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F3C2}', size)
+}
+
+pub fn folder_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F3D7}', size)
+}
+
+pub fn reopen_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F117}', size)
+}
+
+pub fn history_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F292}', size)
+}
+
+pub fn file_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F392}', size)
+}
+
+pub fn new_folder_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F3D3}', size)
+}
+
+pub fn refresh_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F116}', size)
+}
+
+pub fn chevron_right_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F285}', size)
+}
+
+pub fn chevron_left_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F284}', size)
+}
+
+pub fn chevron_down_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F282}', size)
+}
+
+pub fn sidebar_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F45F}', size)
+}
+
+pub fn split_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F460}', size)
+}
+
+pub fn list_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F479}', size)
+}
+
+pub fn pin_icon<'a>(size: impl Into<Pixels>) -> Element<'a, Message> {
+	icon('\u{F4EC}', size)
+}
+
 fn icon<'a>(codepoint: char, size: impl Into<Pixels>) -> Element<'a, Message> {
 	const ICON_FONT: Font = Font::with_name("bootstrap-icons");
 
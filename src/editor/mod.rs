@@ -1,2 +1,3 @@
 pub mod components;
+pub mod highlighter;
 pub mod icons;
\ No newline at end of file
@@ -0,0 +1,270 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Encoding {
+	#[default]
+	Utf8,
+	Utf16Le,
+	Utf16Be,
+	Windows1250,
+	Windows1251,
+	Windows1252,
+	Latin1,
+	Latin9,
+}
+
+impl Encoding {
+	pub const ALL: [Encoding; 8] = [
+		Encoding::Utf8,
+		Encoding::Utf16Le,
+		Encoding::Utf16Be,
+		Encoding::Windows1250,
+		Encoding::Windows1251,
+		Encoding::Windows1252,
+		Encoding::Latin1,
+		Encoding::Latin9,
+	];
+
+	#[must_use]
+	pub fn bom(self) -> &'static [u8] {
+		match self {
+			Encoding::Utf8 => b"\xEF\xBB\xBF",
+			Encoding::Utf16Le => b"\xFF\xFE",
+			Encoding::Utf16Be => b"\xFE\xFF",
+			_ => b"",
+		}
+	}
+
+	#[must_use]
+	pub fn is_unicode(self) -> bool {
+		!self.bom().is_empty()
+	}
+
+	/// The `encoding_rs` codec for legacy single-byte encodings.
+	///
+	/// Latin-1 is handled separately, since `encoding_rs` follows the WHATWG
+	/// spec and treats it as an alias of Windows-1252.
+	fn codec(self) -> Option<&'static encoding_rs::Encoding> {
+		match self {
+			Encoding::Windows1250 => Some(encoding_rs::WINDOWS_1250),
+			Encoding::Windows1251 => Some(encoding_rs::WINDOWS_1251),
+			Encoding::Windows1252 => Some(encoding_rs::WINDOWS_1252),
+			Encoding::Latin9 => Some(encoding_rs::ISO_8859_15),
+			_ => None,
+		}
+	}
+
+	fn from_codec(codec: &'static encoding_rs::Encoding) -> Option<Self> {
+		Encoding::ALL
+			.into_iter()
+			.find(|encoding| encoding.codec() == Some(codec))
+	}
+}
+
+impl fmt::Display for Encoding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Encoding::Utf8 => write!(f, "UTF-8"),
+			Encoding::Utf16Le => write!(f, "UTF-16 LE"),
+			Encoding::Utf16Be => write!(f, "UTF-16 BE"),
+			Encoding::Windows1250 => write!(f, "Windows-1250"),
+			Encoding::Windows1251 => write!(f, "Windows-1251"),
+			Encoding::Windows1252 => write!(f, "Windows-1252"),
+			Encoding::Latin1 => write!(f, "ISO-8859-1 (Latin-1)"),
+			Encoding::Latin9 => write!(f, "ISO-8859-15 (Latin-9)"),
+		}
+	}
+}
+
+/// Decodes the given bytes, detecting the encoding if none is given.
+///
+/// Returns the decoded text, the encoding used and whether a byte order mark
+/// was present. Malformed sequences are replaced with U+FFFD.
+#[must_use]
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> (String, Encoding, bool) {
+	let encoding = encoding.unwrap_or_else(|| detect(bytes));
+
+	let bom = encoding.is_unicode() && bytes.starts_with(encoding.bom());
+	let bytes = if bom {
+		&bytes[encoding.bom().len()..]
+	} else {
+		bytes
+	};
+
+	let text = match encoding {
+		Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+		Encoding::Utf16Le => encoding_rs::UTF_16LE
+			.decode_without_bom_handling(bytes)
+			.0
+			.into_owned(),
+		Encoding::Utf16Be => encoding_rs::UTF_16BE
+			.decode_without_bom_handling(bytes)
+			.0
+			.into_owned(),
+		Encoding::Latin1 => bytes.iter().map(|&byte| char::from(byte)).collect(),
+		_ => encoding
+			.codec()
+			.unwrap_or(encoding_rs::WINDOWS_1252)
+			.decode_without_bom_handling(bytes)
+			.0
+			.into_owned(),
+	};
+
+	(text, encoding, bom)
+}
+
+/// Encodes the given text, failing if it contains characters the encoding
+/// can't represent.
+pub fn encode(text: &str, encoding: Encoding, bom: bool) -> Option<Vec<u8>> {
+	let mut bytes = Vec::with_capacity(text.len() + 3);
+
+	if bom {
+		bytes.extend_from_slice(encoding.bom());
+	}
+
+	match encoding {
+		Encoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+		Encoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+		Encoding::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+		Encoding::Latin1 => {
+			for char in text.chars() {
+				bytes.push(u8::try_from(u32::from(char)).ok()?);
+			}
+		}
+		_ => {
+			let (encoded, _, unmappable) = encoding.codec()?.encode(text);
+
+			if unmappable {
+				return None;
+			}
+
+			bytes.extend_from_slice(&encoded);
+		}
+	}
+
+	Some(bytes)
+}
+
+/// Whether the bytes look like a binary file rather than text.
+pub fn is_binary(bytes: &[u8]) -> bool {
+	let head = &bytes[..bytes.len().min(8000)];
+
+	// UTF-16 text is full of zero bytes, with or without a byte order mark
+	head.contains(&0)
+		&& !head.starts_with(b"\xFF\xFE")
+		&& !head.starts_with(b"\xFE\xFF")
+		&& detect_utf16(head).is_none()
+}
+
+fn detect(bytes: &[u8]) -> Encoding {
+	if let Some(encoding) = [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+		.into_iter()
+		.find(|encoding| bytes.starts_with(encoding.bom()))
+	{
+		return encoding;
+	}
+
+	// ASCII text in UTF-16 is also valid UTF-8, so this has to be checked first
+	if let Some(encoding) = detect_utf16(bytes) {
+		return encoding;
+	}
+
+	if std::str::from_utf8(bytes).is_ok() {
+		return Encoding::Utf8;
+	}
+
+	let mut detector = chardetng::EncodingDetector::new();
+	detector.feed(bytes, true);
+
+	match Encoding::from_codec(detector.guess(None, false)) {
+		Some(Encoding::Windows1252) | None => {
+			// Bytes left undefined by Windows-1252 only make sense as Latin-1 control codes
+			if bytes
+				.iter()
+				.any(|byte| matches!(byte, 0x81 | 0x8D | 0x8F | 0x90 | 0x9D))
+			{
+				Encoding::Latin1
+			} else {
+				Encoding::Windows1252
+			}
+		}
+		Some(encoding) => encoding,
+	}
+}
+
+/// Recognizes BOM-less UTF-16 by the zero high bytes of ASCII characters.
+fn detect_utf16(bytes: &[u8]) -> Option<Encoding> {
+	if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+		return None;
+	}
+
+	let pairs = bytes.len() / 2;
+	let (mut even, mut odd) = (0usize, 0usize);
+
+	for pair in bytes.chunks_exact(2) {
+		if pair[0] == 0 {
+			even += 1;
+		}
+		if pair[1] == 0 {
+			odd += 1;
+		}
+	}
+
+	if odd * 3 > pairs && even * 10 < pairs {
+		Some(Encoding::Utf16Le)
+	} else if even * 3 > pairs && odd * 10 < pairs {
+		Some(Encoding::Utf16Be)
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_unicode_by_byte_order_mark() {
+		assert_eq!(decode(b"\xEF\xBB\xBFtext", None), ("text".to_string(), Encoding::Utf8, true));
+		assert_eq!(decode(b"\xFF\xFEh\0i\0", None), ("hi".to_string(), Encoding::Utf16Le, true));
+		assert_eq!(decode(b"\xFE\xFF\0h\0i", None), ("hi".to_string(), Encoding::Utf16Be, true));
+	}
+
+	#[test]
+	fn detects_utf16_without_byte_order_mark() {
+		assert_eq!(decode(b"h\0e\0l\0l\0o\0", None), ("hello".to_string(), Encoding::Utf16Le, false));
+		assert_eq!(decode(b"\0h\0e\0l\0l\0o", None), ("hello".to_string(), Encoding::Utf16Be, false));
+	}
+
+	#[test]
+	fn detects_utf8_and_single_byte_encodings() {
+		assert_eq!(decode("caf\u{e9}".as_bytes(), None), ("caf\u{e9}".to_string(), Encoding::Utf8, false));
+		assert_eq!(decode(b"caf\xE9 cr\xE8me br\xFBl\xE9e", None).1, Encoding::Windows1252);
+		// 0x81 isn't defined in Windows-1252
+		assert_eq!(decode(b"caf\xE9\x81", None).1, Encoding::Latin1);
+	}
+
+	#[test]
+	fn encodes_what_it_decodes() {
+		for encoding in Encoding::ALL {
+			let text = if encoding == Encoding::Windows1251 { "\u{416}\u{43e}" } else { "caf\u{e9}" };
+			let bom = encoding.is_unicode();
+			let bytes = encode(text, encoding, bom).expect("The encoding has the characters");
+
+			assert_eq!(decode(&bytes, Some(encoding)), (text.to_string(), encoding, bom));
+		}
+
+		assert_eq!(encode("\u{3042}", Encoding::Latin1, false), None);
+		assert_eq!(encode("\u{3042}", Encoding::Windows1252, false), None);
+	}
+
+	#[test]
+	fn binary_files_have_zero_bytes() {
+		assert!(is_binary(b"\x7FELF\x02\x01\0\0"));
+		assert!(!is_binary(b"plain text"));
+		assert!(!is_binary(b"\xFF\xFEh\0i\0"));
+		assert!(!is_binary(b"h\0e\0l\0l\0o\0"));
+	}
+}
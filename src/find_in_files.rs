@@ -0,0 +1,311 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+
+use crate::atomic_write::{self, Backup};
+use crate::encoding;
+use crate::search::{LineIndex, Search};
+use crate::{Error, FileId};
+
+/// Files larger than this are skipped when searching a directory.
+const MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Searching stops after this many matches, to keep the results panel responsive.
+const MAX_MATCHES: usize = 10_000;
+
+const MAX_PREVIEW_LENGTH: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+	#[default]
+	OpenTabs,
+	Directory,
+}
+
+impl Scope {
+	pub const ALL: [Scope; 2] = [Scope::OpenTabs, Scope::Directory];
+}
+
+impl fmt::Display for Scope {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Scope::OpenTabs => write!(f, "Open tabs"),
+			Scope::Directory => write!(f, "Folder"),
+		}
+	}
+}
+
+/// Where the matches of a [`FileResults`] were found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+	/// A tab without a file on disk.
+	Tab(FileId),
+	Path(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct ResultMatch {
+	pub line: usize,
+	pub column: usize,
+	pub preview: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileResults {
+	pub location: Location,
+	pub name: String,
+	pub matches: Vec<ResultMatch>,
+}
+
+/// The state of the "Find in Files" panel.
+#[derive(Debug, Clone, Default)]
+pub struct FindInFiles {
+	pub visible: bool,
+	pub search: Search,
+	pub scope: Scope,
+	pub directory: Option<PathBuf>,
+	pub include: String,
+	pub exclude: String,
+	pub results: Vec<FileResults>,
+	pub is_searching: bool,
+	pub status: Option<String>,
+}
+
+impl FindInFiles {
+	pub fn match_count(&self) -> usize {
+		self.results.iter().map(|file| file.matches.len()).sum()
+	}
+}
+
+/// Finds the matches in a single text, with a preview of the line each is on.
+pub fn search_text(search: &Search, text: &str) -> Result<Vec<ResultMatch>, regex::Error> {
+	let index = LineIndex::new(text);
+
+	Ok(search
+		.find(text)?
+		.into_iter()
+		.take(MAX_MATCHES)
+		.map(|found| {
+			let (line, column) = index.position(found.start);
+
+			let preview = text[found.start - column..]
+				.lines()
+				.next()
+				.unwrap_or_default()
+				.trim()
+				.chars()
+				.take(MAX_PREVIEW_LENGTH)
+				.collect();
+
+			ResultMatch { line, column, preview }
+		})
+		.collect())
+}
+
+/// Searches the files under a directory, honouring `.gitignore` files and the
+/// comma-separated include and exclude glob patterns.
+pub async fn search_directory(
+	search: Search,
+	directory: PathBuf,
+	include: String,
+	exclude: String,
+) -> Result<Vec<FileResults>, Error> {
+	tokio::task::spawn_blocking(move || {
+		let mut results = Vec::new();
+		let mut total = 0;
+
+		for path in walk(&directory, &include, &exclude)? {
+			let Ok(bytes) = std::fs::read(&path) else {
+				continue;
+			};
+
+			if encoding::is_binary(&bytes) {
+				continue;
+			}
+
+			let (text, _, _) = encoding::decode(&bytes, None);
+
+			let matches = search_text(&search, &text)
+				.map_err(|error| Error::SearchFailed(error.to_string()))?;
+
+			if matches.is_empty() {
+				continue;
+			}
+
+			total += matches.len();
+
+			results.push(FileResults {
+				name: path
+					.strip_prefix(&directory)
+					.unwrap_or(&path)
+					.display()
+					.to_string(),
+				location: Location::Path(path),
+				matches,
+			});
+
+			if total >= MAX_MATCHES {
+				break;
+			}
+		}
+
+		Ok(results)
+	})
+		.await
+		.map_err(|_| Error::SearchFailed("The search was interrupted".to_string()))?
+}
+
+/// Replaces every match in the given files on disk, keeping their encoding.
+///
+/// Returns the total amount of replacements made.
+pub async fn replace_in_files(search: Search, paths: Vec<PathBuf>, backup: Backup) -> Result<usize, Error> {
+	let mut total = 0;
+
+	for path in paths {
+		let bytes = tokio::fs::read(&path)
+			.await
+			.map_err(|error| Error::IOFailed(error.kind()))?;
+
+		let (text, encoding, bom) = encoding::decode(&bytes, None);
+
+		let (replaced, count) = search
+			.replace_all(&text)
+			.map_err(|error| Error::SearchFailed(error.to_string()))?;
+
+		if count == 0 {
+			continue;
+		}
+
+		let bytes = encoding::encode(&replaced, encoding, bom)
+			.ok_or(Error::EncodingFailed(encoding))?;
+
+		atomic_write::write(path, bytes, backup)
+			.await
+			.map_err(|error| Error::IOFailed(error.kind()))?;
+
+		total += count;
+	}
+
+	Ok(total)
+}
+
+fn walk(directory: &Path, include: &str, exclude: &str) -> Result<Vec<PathBuf>, Error> {
+	let mut overrides = OverrideBuilder::new(directory);
+
+	let patterns = include
+		.split(',')
+		.map(|pattern| pattern.trim().to_string())
+		.chain(exclude.split(',').map(|pattern| format!("!{}", pattern.trim())))
+		.filter(|pattern| !pattern.is_empty() && pattern != "!");
+
+	for pattern in patterns {
+		overrides
+			.add(&pattern)
+			.map_err(|error| Error::SearchFailed(error.to_string()))?;
+	}
+
+	let overrides = overrides
+		.build()
+		.map_err(|error| Error::SearchFailed(error.to_string()))?;
+
+	Ok(WalkBuilder::new(directory)
+		.overrides(overrides)
+		.require_git(false)
+		.max_filesize(Some(MAX_FILE_SIZE))
+		.build()
+		.filter_map(Result::ok)
+		.filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+		.map(ignore::DirEntry::into_path)
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn search(query: &str) -> Search {
+		Search { query: query.to_string(), ..Search::default() }
+	}
+
+	/// A directory under the system's temporary one, removed when dropped.
+	struct Directory(PathBuf);
+
+	impl Directory {
+		fn new(name: &str, files: &[&str]) -> Self {
+			let path = std::env::temp_dir().join(format!("find_in_files_{name}_{}", std::process::id()));
+
+			for file in files {
+				let file = path.join(file);
+
+				std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+				std::fs::write(file, "text").unwrap();
+			}
+
+			Directory(path)
+		}
+
+		fn walk(&self, include: &str, exclude: &str) -> Vec<String> {
+			let mut paths: Vec<String> = walk(&self.0, include, exclude)
+				.unwrap()
+				.into_iter()
+				.map(|path| path.strip_prefix(&self.0).unwrap().to_string_lossy().replace('\\', "/"))
+				.collect();
+
+			paths.sort();
+			paths
+		}
+	}
+
+	impl Drop for Directory {
+		fn drop(&mut self) {
+			let _ = std::fs::remove_dir_all(&self.0);
+		}
+	}
+
+	#[test]
+	fn finds_the_line_and_column_of_matches() {
+		let matches = search_text(&search("needle"), "hay\n  a needle\nneedle").unwrap();
+
+		let positions: Vec<(usize, usize)> = matches.iter().map(|found| (found.line, found.column)).collect();
+
+		assert_eq!(positions, [(1, 4), (2, 0)]);
+		assert_eq!(matches[0].preview, "a needle");
+	}
+
+	#[test]
+	fn previews_are_cut_at_a_character_boundary() {
+		let line = "é".repeat(MAX_PREVIEW_LENGTH * 2);
+		let matches = search_text(&search("é"), &line).unwrap();
+
+		assert_eq!(matches[0].preview.chars().count(), MAX_PREVIEW_LENGTH);
+	}
+
+	#[test]
+	fn reports_invalid_regular_expressions() {
+		let mut search = search("(");
+		search.options.regex = true;
+
+		assert!(search_text(&search, "(").is_err());
+	}
+
+	#[test]
+	fn walk_honours_include_and_exclude_patterns() {
+		let directory = Directory::new("patterns", &["a.rs", "b.txt", "target/c.rs"]);
+
+		assert_eq!(directory.walk("", ""), ["a.rs", "b.txt", "target/c.rs"]);
+		assert_eq!(directory.walk("*.rs", ""), ["a.rs", "target/c.rs"]);
+		assert_eq!(directory.walk("*.rs", "target"), ["a.rs"]);
+		assert_eq!(directory.walk("", "*.txt, target"), ["a.rs"]);
+	}
+
+	#[test]
+	fn walk_honours_gitignore_files() {
+		let directory = Directory::new("gitignore", &["kept.rs", "ignored.log"]);
+
+		std::fs::write(directory.0.join(".gitignore"), "*.log\n").unwrap();
+
+		assert_eq!(directory.walk("", ""), ["kept.rs"]);
+	}
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::Encoding;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LineEnding {
+	#[default]
+	Lf,
+	CrLf,
+	Cr,
+}
+
+impl LineEnding {
+	pub const ALL: [LineEnding; 3] = [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+
+	#[must_use]
+	pub fn as_str(self) -> &'static str {
+		match self {
+			LineEnding::Lf => "\n",
+			LineEnding::CrLf => "\r\n",
+			LineEnding::Cr => "\r",
+		}
+	}
+
+	/// Detects the dominant line ending of the given text, defaulting to LF.
+	#[must_use]
+	pub fn detect(text: &str) -> Self {
+		let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+		let mut chars = text.chars().peekable();
+
+		while let Some(char) = chars.next() {
+			match char {
+				'\r' if chars.peek() == Some(&'\n') => {
+					chars.next();
+					crlf += 1;
+				}
+				'\r' => cr += 1,
+				'\n' => lf += 1,
+				_ => {}
+			}
+		}
+
+		if crlf > lf && crlf >= cr {
+			LineEnding::CrLf
+		} else if cr > lf && cr > crlf {
+			LineEnding::Cr
+		} else {
+			LineEnding::Lf
+		}
+	}
+
+	/// Converts text with `'\n'` line breaks to this line ending.
+	#[must_use]
+	pub fn apply(self, text: &str) -> String {
+		match self {
+			LineEnding::Lf => text.to_string(),
+			_ => text.replace('\n', self.as_str()),
+		}
+	}
+}
+
+impl fmt::Display for LineEnding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LineEnding::Lf => write!(f, "LF"),
+			LineEnding::CrLf => write!(f, "CRLF"),
+			LineEnding::Cr => write!(f, "CR"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Indentation {
+	Tabs,
+	Spaces(usize),
+}
+
+impl Default for Indentation {
+	fn default() -> Self {
+		Indentation::Spaces(4)
+	}
+}
+
+impl Indentation {
+	/// Detects whether the text is indented with tabs or spaces, and how wide
+	/// a level of space indentation is.
+	#[must_use]
+	pub fn detect(text: &str) -> Self {
+		let mut tabs = 0usize;
+		let mut spaces = 0usize;
+		let mut width: Option<usize> = None;
+
+		for line in text.lines() {
+			if line.starts_with('\t') {
+				tabs += 1;
+			} else if line.starts_with(' ') {
+				let leading = line.len() - line.trim_start_matches(' ').len();
+
+				// Single spaces are usually alignment (e.g. in doc comments), not indentation
+				if leading > 1 && !line.trim_start().is_empty() {
+					spaces += 1;
+					width = Some(width.map_or(leading, |width| width.min(leading)));
+				}
+			}
+		}
+
+		if tabs > spaces {
+			Indentation::Tabs
+		} else {
+			width.map_or_else(Indentation::default, |width| Indentation::Spaces(width.min(8)))
+		}
+	}
+}
+
+impl fmt::Display for Indentation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Indentation::Tabs => write!(f, "Tabs"),
+			Indentation::Spaces(width) => write!(f, "Spaces: {width}"),
+		}
+	}
+}
+
+/// The on-disk representation of a file that isn't visible in the editor buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextFormat {
+	pub line_ending: LineEnding,
+	pub indentation: Indentation,
+	pub encoding: Encoding,
+	pub bom: bool,
+	/// Whether the file ends with a line break, which is kept when saving even
+	/// if the empty last line gets deleted.
+	#[serde(default = "default_final_newline")]
+	pub final_newline: bool,
+}
+
+impl Default for TextFormat {
+	fn default() -> Self {
+		TextFormat {
+			line_ending: LineEnding::default(),
+			indentation: Indentation::default(),
+			encoding: Encoding::default(),
+			bom: false,
+			final_newline: true,
+		}
+	}
+}
+
+fn default_final_newline() -> bool {
+	true
+}
+
+impl TextFormat {
+	#[must_use]
+	pub fn detect(text: &str) -> Self {
+		TextFormat {
+			line_ending: LineEnding::detect(text),
+			indentation: Indentation::detect(text),
+			final_newline: text.is_empty() || text.ends_with(['\n', '\r']),
+			..TextFormat::default()
+		}
+	}
+
+	/// Converts the text of the editor buffer to how it is written to disk,
+	/// apart from its encoding.
+	#[must_use]
+	pub fn apply(&self, text: &str) -> String {
+		let mut text = self.line_ending.apply(text);
+
+		if self.final_newline && !text.is_empty() && !text.ends_with(self.line_ending.as_str()) {
+			text.push_str(self.line_ending.as_str());
+		}
+
+		text
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_the_dominant_line_ending() {
+		assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+		assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+		assert_eq!(LineEnding::detect("a\rb\r"), LineEnding::Cr);
+		assert_eq!(LineEnding::detect("no line breaks"), LineEnding::Lf);
+	}
+
+	#[test]
+	fn detects_indentation() {
+		assert_eq!(Indentation::detect("fn a() {\n\tb();\n}\n"), Indentation::Tabs);
+		assert_eq!(Indentation::detect("a:\n  b:\n    c\n"), Indentation::Spaces(2));
+		assert_eq!(Indentation::detect("/**\n * doc\n */\n"), Indentation::default());
+	}
+
+	#[test]
+	fn keeps_a_missing_final_newline() {
+		let format = TextFormat::detect("a\nb");
+
+		assert!(!format.final_newline);
+		assert_eq!(format.apply("a\nb"), "a\nb");
+		assert_eq!(format.apply("a\nb\n"), "a\nb\n");
+	}
+
+	#[test]
+	fn keeps_the_final_newline() {
+		let format = TextFormat {
+			line_ending: LineEnding::CrLf,
+			..TextFormat::detect("a\r\nb\r\n")
+		};
+
+		assert!(format.final_newline);
+		assert_eq!(format.apply("a\nb\n"), "a\r\nb\r\n");
+		assert_eq!(format.apply("a\nb"), "a\r\nb\r\n");
+		assert_eq!(format.apply(""), "");
+	}
+}
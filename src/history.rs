@@ -0,0 +1,372 @@
+use std::ops::Range;
+
+use iced::widget::text_editor::{Action, Content, Edit};
+
+pub const DEFAULT_LIMIT: usize = 100;
+
+pub const LIMITS: [usize; 5] = [25, 50, 100, 250, 1000];
+
+#[must_use]
+pub fn default_limit() -> usize {
+	DEFAULT_LIMIT
+}
+
+/// The state of a [`Content`] at some point in its history.
+pub struct Snapshot {
+	pub text: String,
+	pub cursor: (usize, usize),
+	state: u64,
+}
+
+/// A state of a [`Content`], stored as the change that leads to it from the
+/// state next to it in the history, so that a step only takes as much memory
+/// as the text that changed.
+struct Step {
+	change: Change,
+	cursor: (usize, usize),
+	state: u64,
+}
+
+/// Replaces a byte range of a text.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+	range: Range<usize>,
+	text: String,
+}
+
+impl Change {
+	/// The change that turns one text into the other, covering only what lies
+	/// between the start and the end they have in common.
+	pub fn between(from: &str, to: &str) -> Self {
+		let mut start = from
+			.bytes()
+			.zip(to.bytes())
+			.take_while(|(from, to)| from == to)
+			.count();
+
+		while !from.is_char_boundary(start) || !to.is_char_boundary(start) {
+			start -= 1;
+		}
+
+		let mut end = from
+			.bytes()
+			.rev()
+			.zip(to.bytes().rev())
+			.take(from.len().min(to.len()) - start)
+			.take_while(|(from, to)| from == to)
+			.count();
+
+		while !from.is_char_boundary(from.len() - end) || !to.is_char_boundary(to.len() - end) {
+			end -= 1;
+		}
+
+		Change {
+			range: start..from.len() - end,
+			text: to[start..to.len() - end].to_string(),
+		}
+	}
+
+	pub fn apply(&self, text: &str) -> String {
+		let mut changed = String::with_capacity(text.len() - self.range.len() + self.text.len());
+
+		changed.push_str(&text[..self.range.start]);
+		changed.push_str(&self.text);
+		changed.push_str(&text[self.range.end..]);
+
+		changed
+	}
+
+	/// Where an offset in the text before the change ends up after it. Offsets
+	/// within the replaced range move to the end of the new text.
+	pub fn shift(&self, offset: usize) -> usize {
+		if offset <= self.range.start {
+			offset
+		} else if offset >= self.range.end {
+			offset - self.range.len() + self.text.len()
+		} else {
+			self.range.start + self.text.len()
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+	Typing,
+	Deleting,
+}
+
+/// The undo and redo stacks of a single file.
+///
+/// Every distinct state of the content gets its own id, which is how the
+/// history knows whether undoing or redoing brought the content back to the
+/// state it was last saved in.
+///
+/// Only the state the next undo goes back to is kept in full. The states
+/// before it are each kept as the change from the state after them, and the
+/// states that can be redone as the change from the state before them.
+pub struct History {
+	last: Option<Snapshot>,
+	undo: Vec<Step>,
+	redo: Vec<Step>,
+	group: Option<Group>,
+	state: u64,
+	next_state: u64,
+	clean: Option<u64>,
+}
+
+impl Default for History {
+	fn default() -> Self {
+		History {
+			last: None,
+			undo: Vec::new(),
+			redo: Vec::new(),
+			group: None,
+			state: 0,
+			next_state: 1,
+			clean: Some(0),
+		}
+	}
+}
+
+impl History {
+	/// Creates a history for content that doesn't match anything on disk.
+	pub fn unsaved() -> Self {
+		History {
+			clean: None,
+			..History::default()
+		}
+	}
+
+	/// Records the content before the given action is performed on it.
+	///
+	/// Consecutive typing or deleting is grouped into a single step, while
+	/// anything else, including edits that replace a selection, starts a new one.
+	pub fn record(&mut self, content: &Content, action: &Action, limit: usize) {
+		let Action::Edit(edit) = action else {
+			if !matches!(action, Action::Scroll { .. }) {
+				self.group = None;
+			}
+
+			return;
+		};
+
+		let group = if content.selection().is_some() {
+			None
+		} else {
+			match edit {
+				Edit::Insert(_) => Some(Group::Typing),
+				Edit::Backspace | Edit::Delete => Some(Group::Deleting),
+				Edit::Paste(_) | Edit::Enter => None,
+			}
+		};
+
+		if group.is_none() || group != self.group {
+			self.checkpoint(content, limit);
+		}
+
+		self.group = group;
+		self.advance();
+	}
+
+	/// Records the content before it gets replaced as a whole, making the
+	/// replacement a single step.
+	pub fn record_replacement(&mut self, content: &Content, limit: usize) {
+		self.checkpoint(content, limit);
+		self.group = None;
+		self.advance();
+	}
+
+	/// Makes the next edit start a new step, e.g. after the cursor was moved
+	/// without an action.
+	pub fn end_group(&mut self) {
+		self.group = None;
+	}
+
+	pub fn undo(&mut self, content: &Content) -> Option<Snapshot> {
+		let target = self.last.take()?;
+		let current = self.snapshot(content);
+
+		self.redo.push(Step {
+			change: Change::between(&target.text, &current.text),
+			cursor: current.cursor,
+			state: current.state,
+		});
+
+		self.last = self.undo.pop().map(|step| Snapshot {
+			text: step.change.apply(&target.text),
+			cursor: step.cursor,
+			state: step.state,
+		});
+
+		self.restore(target.state);
+
+		Some(target)
+	}
+
+	pub fn redo(&mut self, content: &Content) -> Option<Snapshot> {
+		let step = self.redo.pop()?;
+		let current = self.snapshot(content);
+		let text = step.change.apply(&current.text);
+
+		self.push_undo(current);
+		self.restore(step.state);
+
+		Some(Snapshot {
+			text,
+			cursor: step.cursor,
+			state: step.state,
+		})
+	}
+
+	pub fn can_undo(&self) -> bool {
+		self.last.is_some()
+	}
+
+	pub fn can_redo(&self) -> bool {
+		!self.redo.is_empty()
+	}
+
+	/// Marks the current state as the one on disk.
+	pub fn mark_clean(&mut self) {
+		self.clean = Some(self.state);
+		self.group = None;
+	}
+
+	/// Forgets the saved state, for changes the history doesn't track.
+	pub fn mark_dirty(&mut self) {
+		self.clean = None;
+	}
+
+	pub fn is_clean(&self) -> bool {
+		self.clean == Some(self.state)
+	}
+
+	fn checkpoint(&mut self, content: &Content, limit: usize) {
+		let snapshot = self.snapshot(content);
+
+		self.push_undo(snapshot);
+		self.redo.clear();
+
+		// The state kept in full counts as a step too
+		let kept = limit.saturating_sub(1);
+
+		if self.undo.len() > kept {
+			self.undo.drain(..self.undo.len() - kept);
+		}
+	}
+
+	/// Makes the given state the one the next undo goes back to.
+	fn push_undo(&mut self, snapshot: Snapshot) {
+		if let Some(last) = self.last.take() {
+			self.undo.push(Step {
+				change: Change::between(&snapshot.text, &last.text),
+				cursor: last.cursor,
+				state: last.state,
+			});
+		}
+
+		self.last = Some(snapshot);
+	}
+
+	fn advance(&mut self) {
+		self.state = self.next_state;
+		self.next_state += 1;
+	}
+
+	fn snapshot(&self, content: &Content) -> Snapshot {
+		Snapshot {
+			text: crate::buffer_text(content),
+			cursor: content.cursor_position(),
+			state: self.state,
+		}
+	}
+
+	fn restore(&mut self, state: u64) {
+		self.state = state;
+		self.group = None;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn change_covers_only_the_difference() {
+		let change = Change::between("let value = 1;", "let other = 1;");
+
+		assert_eq!(change, Change { range: 4..9, text: "other".to_string() });
+		assert_eq!(change.apply("let value = 1;"), "let other = 1;");
+	}
+
+	#[test]
+	fn shift_moves_offsets_after_the_change() {
+		let change = Change::between("one two three", "one 2 three");
+
+		assert_eq!(change.shift(2), 2);
+		assert_eq!(change.shift(6), 5);
+		assert_eq!(change.shift(9), 7);
+	}
+
+	#[test]
+	fn change_keeps_characters_whole() {
+		// "é" and "è" share their first byte
+		let change = Change::between("caf\u{e9}", "caf\u{e8}");
+
+		assert_eq!(change, Change { range: 3..5, text: "\u{e8}".to_string() });
+
+		assert_eq!(Change::between("aaa", "aa").apply("aaa"), "aa");
+		assert_eq!(Change::between("", "new").apply(""), "new");
+	}
+
+	#[test]
+	fn undo_and_redo_go_through_every_step() {
+		let mut content = Content::with_text("one");
+		let mut history = History::default();
+
+		for text in ["one two", "one two three"] {
+			history.record_replacement(&content, DEFAULT_LIMIT);
+			content = Content::with_text(text);
+		}
+
+		for expected in ["one two", "one"] {
+			let snapshot = history.undo(&content).expect("There's a step to undo");
+
+			assert_eq!(snapshot.text, expected);
+			content = Content::with_text(&snapshot.text);
+		}
+
+		assert!(history.undo(&content).is_none());
+		assert!(history.is_clean());
+
+		for expected in ["one two", "one two three"] {
+			let snapshot = history.redo(&content).expect("There's a step to redo");
+
+			assert_eq!(snapshot.text, expected);
+			content = Content::with_text(&snapshot.text);
+		}
+
+		assert!(history.redo(&content).is_none());
+		assert_eq!(history.undo(&content).map(|snapshot| snapshot.text).as_deref(), Some("one two"));
+	}
+
+	#[test]
+	fn limit_drops_the_oldest_steps() {
+		let mut content = Content::with_text("0");
+		let mut history = History::default();
+
+		for step in 1..=5 {
+			history.record_replacement(&content, 3);
+			content = Content::with_text(&step.to_string());
+		}
+
+		let mut undone = Vec::new();
+
+		while let Some(snapshot) = history.undo(&content) {
+			content = Content::with_text(&snapshot.text);
+			undone.push(snapshot.text);
+		}
+
+		assert_eq!(undone, ["4", "3", "2"]);
+	}
+}
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use iced::Subscription;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::FileArgument;
+use crate::Message;
+
+/// How long a new launch gets to send its request before it's dropped, so a
+/// stuck client can't stop the editor from taking other requests.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The most a request may take up. Even thousands of paths fit well within
+/// it, and anything larger is dropped rather than read into memory.
+const MAX_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// The folder holding the socket. It's in the runtime or cache folder of the
+/// user rather than a shared temporary folder, where another user could put
+/// a socket of their own in its place.
+static DIRECTORY: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+	use std::os::unix::fs::DirBuilderExt;
+
+	let directory = dirs::runtime_dir()
+		.or_else(dirs::cache_dir)?
+		.join("multi_tab_text_editor");
+
+	std::fs::DirBuilder::new()
+		.recursive(true)
+		.mode(0o700)
+		.create(&directory)
+		.ok()?;
+
+	Some(directory)
+});
+
+/// Where the running editor listens for files opened by other launches.
+static SOCKET_PATH: LazyLock<Option<PathBuf>> =
+	LazyLock::new(|| DIRECTORY.as_ref().map(|directory| directory.join("instance.sock")));
+
+/// Held by the editor that listens on the socket, so that two editors
+/// starting at once don't take the socket from each other.
+static LOCK_PATH: LazyLock<Option<PathBuf>> =
+	LazyLock::new(|| DIRECTORY.as_ref().map(|directory| directory.join("instance.lock")));
+
+/// What a new launch asks the running editor to do.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Request {
+	pub files: Vec<FileArgument>,
+}
+
+impl Request {
+	pub fn new(files: &[FileArgument]) -> Self {
+		// The running editor may have been started in another folder
+		let files = files
+			.iter()
+			.map(|file| FileArgument {
+				path: std::path::absolute(&file.path).unwrap_or_else(|_| file.path.clone()),
+				position: file.position,
+			})
+			.collect();
+
+		Request { files }
+	}
+}
+
+pub fn is_running() -> bool {
+	SOCKET_PATH
+		.as_ref()
+		.is_some_and(|path| std::os::unix::net::UnixStream::connect(path).is_ok())
+}
+
+/// Hands the request over to an editor that is already running.
+///
+/// Returns `false` if there is no running editor to take it.
+pub fn forward(request: &Request) -> bool {
+	use std::io::Write;
+	use std::os::unix::net::UnixStream;
+
+	let Some(Ok(mut stream)) = SOCKET_PATH.as_ref().map(UnixStream::connect) else {
+		return false;
+	};
+
+	let Ok(request) = serde_json::to_vec(request) else {
+		return false;
+	};
+
+	stream.write_all(&request).is_ok()
+}
+
+/// Listens for requests from new launches, opening their files as tabs.
+pub fn listen() -> Subscription<Message> {
+	use iced::futures::SinkExt;
+	use tokio::io::AsyncReadExt;
+	use tokio::net::UnixListener;
+
+	struct Listener;
+
+	iced::subscription::channel(std::any::TypeId::of::<Listener>(), 100, |mut output| async move {
+		// The lock lives as long as the listener. Without it, another editor
+		// is listening already, and its socket is left alone.
+		let lock = LOCK_PATH
+			.as_ref()
+			.and_then(|path| File::create(path).ok())
+			.filter(|lock| lock.try_lock().is_ok());
+
+		let listener = match (&lock, &*SOCKET_PATH) {
+			(Some(_), Some(path)) => {
+				// Only an editor holding the lock listens, so a socket left here is stale
+				let _ = std::fs::remove_file(path);
+
+				match UnixListener::bind(path) {
+					Ok(listener) => Some(listener),
+					Err(error) => {
+						eprintln!("Failed to listen for files from other launches: {error}");
+						None
+					}
+				}
+			}
+			_ => None,
+		};
+
+		loop {
+			let Some(listener) = &listener else {
+				std::future::pending::<()>().await;
+				continue;
+			};
+
+			let Ok((stream, _)) = listener.accept().await else {
+				continue;
+			};
+
+			let mut stream = stream.take(MAX_REQUEST_BYTES);
+			let mut request = Vec::new();
+
+			let Ok(Ok(length)) = tokio::time::timeout(READ_TIMEOUT, stream.read_to_end(&mut request)).await else {
+				continue;
+			};
+
+			if length as u64 >= MAX_REQUEST_BYTES {
+				continue;
+			}
+
+			let Ok(request) = serde_json::from_slice::<Request>(&request) else {
+				continue;
+			};
+
+			for file in request.files {
+				let result = crate::load_file(&file.path).await;
+
+				let position = result
+					.as_ref()
+					.map_or(file.position, |(_, text, _)| file.position_in(text));
+
+				let _ = output.send(Message::FileOpenedAt(result, position)).await;
+			}
+
+			let _ = output.send(Message::FocusWindow).await;
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requests_hold_absolute_paths() {
+		let request = Request::new(&[FileArgument {
+			path: PathBuf::from("notes.txt"),
+			position: (1, 2),
+		}]);
+
+		assert_eq!(request.files[0].path, std::env::current_dir().unwrap().join("notes.txt"));
+		assert_eq!(request.files[0].position, (1, 2));
+	}
+}
@@ -0,0 +1,734 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use iced::widget::pane_grid::Axis;
+use iced::widget::text_editor::Motion;
+
+use crate::{Message, ModalType};
+
+/// An editor command that can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+	New,
+	Open,
+	OpenFolder,
+	QuickOpen,
+	Save,
+	SaveAs,
+	Close,
+	ReopenClosedTab,
+	NextTab,
+	PreviousTab,
+	NextRecentTab,
+	PreviousRecentTab,
+	Settings,
+	Undo,
+	Redo,
+	Find,
+	FindNext,
+	FindPrevious,
+	FindInFiles,
+	ToggleSidebar,
+	SplitRight,
+	SplitDown,
+	CommandPalette,
+	Dismiss,
+}
+
+impl Action {
+	pub const ALL: [Action; 24] = [
+		Action::New,
+		Action::Open,
+		Action::OpenFolder,
+		Action::QuickOpen,
+		Action::Save,
+		Action::SaveAs,
+		Action::Close,
+		Action::ReopenClosedTab,
+		Action::NextTab,
+		Action::PreviousTab,
+		Action::NextRecentTab,
+		Action::PreviousRecentTab,
+		Action::Settings,
+		Action::Undo,
+		Action::Redo,
+		Action::Find,
+		Action::FindNext,
+		Action::FindPrevious,
+		Action::FindInFiles,
+		Action::ToggleSidebar,
+		Action::SplitRight,
+		Action::SplitDown,
+		Action::CommandPalette,
+		Action::Dismiss,
+	];
+
+	/// The name used for the action in the configuration file.
+	#[must_use]
+	pub fn name(self) -> &'static str {
+		match self {
+			Action::New => "new",
+			Action::Open => "open",
+			Action::OpenFolder => "open_folder",
+			Action::QuickOpen => "quick_open",
+			Action::Save => "save",
+			Action::SaveAs => "save_as",
+			Action::Close => "close",
+			Action::ReopenClosedTab => "reopen_closed_tab",
+			Action::NextTab => "next_tab",
+			Action::PreviousTab => "previous_tab",
+			Action::NextRecentTab => "next_recent_tab",
+			Action::PreviousRecentTab => "previous_recent_tab",
+			Action::Settings => "settings",
+			Action::Undo => "undo",
+			Action::Redo => "redo",
+			Action::Find => "find",
+			Action::FindNext => "find_next",
+			Action::FindPrevious => "find_previous",
+			Action::FindInFiles => "find_in_files",
+			Action::ToggleSidebar => "toggle_sidebar",
+			Action::SplitRight => "split_right",
+			Action::SplitDown => "split_down",
+			Action::CommandPalette => "command_palette",
+			Action::Dismiss => "dismiss",
+		}
+	}
+
+	#[must_use]
+	pub fn from_name(name: &str) -> Option<Self> {
+		Action::ALL.into_iter().find(|action| action.name() == name)
+	}
+
+	pub fn message(self) -> Message {
+		match self {
+			Action::New => Message::New,
+			Action::Open => Message::Open,
+			Action::OpenFolder => Message::OpenFolder,
+			Action::QuickOpen => Message::ShowQuickOpen,
+			Action::Save => Message::Save,
+			Action::SaveAs => Message::SaveAs,
+			Action::Close => Message::Close,
+			Action::ReopenClosedTab => Message::ReopenClosedTab,
+			Action::NextTab => Message::SelectNextFile,
+			Action::PreviousTab => Message::SelectPreviousFile,
+			Action::NextRecentTab => Message::NextRecentTab,
+			Action::PreviousRecentTab => Message::PreviousRecentTab,
+			Action::Settings => Message::ShowModal(ModalType::Settings),
+			Action::Undo => Message::Undo,
+			Action::Redo => Message::Redo,
+			Action::Find => Message::ShowSearch,
+			Action::FindNext => Message::FindNext,
+			Action::FindPrevious => Message::FindPrevious,
+			Action::FindInFiles => Message::ShowFindInFiles,
+			Action::ToggleSidebar => Message::ToggleSidebar,
+			Action::SplitRight => Message::SplitPane(Axis::Vertical),
+			Action::SplitDown => Message::SplitPane(Axis::Horizontal),
+			Action::CommandPalette => Message::ShowCommandPalette,
+			Action::Dismiss => Message::Dismiss,
+		}
+	}
+}
+
+impl fmt::Display for Action {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Action::New => write!(f, "New File"),
+			Action::Open => write!(f, "Open File"),
+			Action::OpenFolder => write!(f, "Open Folder"),
+			Action::QuickOpen => write!(f, "Go to File"),
+			Action::Save => write!(f, "Save"),
+			Action::SaveAs => write!(f, "Save As"),
+			Action::Close => write!(f, "Close Tab"),
+			Action::ReopenClosedTab => write!(f, "Reopen Closed Tab"),
+			Action::NextTab => write!(f, "Next Tab"),
+			Action::PreviousTab => write!(f, "Previous Tab"),
+			Action::NextRecentTab => write!(f, "Next Recently Used Tab"),
+			Action::PreviousRecentTab => write!(f, "Previous Recently Used Tab"),
+			Action::Settings => write!(f, "Open Settings"),
+			Action::Undo => write!(f, "Undo"),
+			Action::Redo => write!(f, "Redo"),
+			Action::Find => write!(f, "Find and Replace"),
+			Action::FindNext => write!(f, "Find Next"),
+			Action::FindPrevious => write!(f, "Find Previous"),
+			Action::FindInFiles => write!(f, "Find in Files"),
+			Action::ToggleSidebar => write!(f, "Toggle Sidebar"),
+			Action::SplitRight => write!(f, "Split Editor Right"),
+			Action::SplitDown => write!(f, "Split Editor Down"),
+			Action::CommandPalette => write!(f, "Show All Commands"),
+			Action::Dismiss => write!(f, "Close Dialog or Panel"),
+		}
+	}
+}
+
+/// A key pressed together with some modifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+	pub key: Key,
+	pub modifiers: Modifiers,
+}
+
+impl Chord {
+	pub fn new(modifiers: Modifiers, key: Key) -> Self {
+		Chord {
+			key: normalize(key),
+			modifiers,
+		}
+	}
+
+	fn character(modifiers: Modifiers, character: &str) -> Self {
+		Chord::new(modifiers, Key::Character(character.into()))
+	}
+
+	fn named(modifiers: Modifiers, named: Named) -> Self {
+		Chord::new(modifiers, Key::Named(named))
+	}
+
+	/// The chord that makes the editor perform a cursor motion.
+	pub fn for_motion(modifiers: Modifiers, motion: Motion) -> Self {
+		let named = match motion {
+			Motion::Left | Motion::WordLeft => Named::ArrowLeft,
+			Motion::Right | Motion::WordRight => Named::ArrowRight,
+			Motion::Up => Named::ArrowUp,
+			Motion::Down => Named::ArrowDown,
+			Motion::Home | Motion::DocumentStart => Named::Home,
+			Motion::End | Motion::DocumentEnd => Named::End,
+			Motion::PageUp => Named::PageUp,
+			Motion::PageDown => Named::PageDown,
+		};
+
+		Chord::named(modifiers, named)
+	}
+
+	/// Whether the key is a modifier, which can't be bound on its own.
+	pub fn is_modifier(key: &Key) -> bool {
+		matches!(
+			key,
+			Key::Named(
+				Named::Control
+					| Named::Shift
+					| Named::Alt
+					| Named::AltGraph
+					| Named::Super
+					| Named::Meta
+					| Named::Hyper
+			)
+		)
+	}
+
+	/// Whether a text input uses the chord for typing or moving the cursor.
+	/// Text inputs keep these, even if they're bound.
+	pub fn is_typing(&self) -> bool {
+		if self.modifiers.control() || self.modifiers.alt() || self.modifiers.logo() {
+			return false;
+		}
+
+		matches!(
+			self.key,
+			Key::Character(_)
+				| Key::Named(
+					Named::Space
+						| Named::Enter
+						| Named::Tab
+						| Named::Backspace
+						| Named::Delete
+						| Named::ArrowUp
+						| Named::ArrowDown
+						| Named::ArrowLeft
+						| Named::ArrowRight
+						| Named::Home
+						| Named::End
+						| Named::PageUp
+						| Named::PageDown
+				)
+		)
+	}
+}
+
+impl fmt::Display for Chord {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.modifiers.control() {
+			write!(f, "Ctrl+")?;
+		}
+		if self.modifiers.logo() {
+			write!(f, "{}+", if cfg!(target_os = "macos") { "Cmd" } else { "Super" })?;
+		}
+		if self.modifiers.alt() {
+			write!(f, "Alt+")?;
+		}
+		if self.modifiers.shift() {
+			write!(f, "Shift+")?;
+		}
+
+		match &self.key {
+			Key::Named(Named::Space) => write!(f, "Space"),
+			Key::Named(named) => write!(f, "{named:?}"),
+			Key::Character(character) => write!(f, "{}", character.to_uppercase()),
+			Key::Unidentified => write!(f, "?"),
+		}
+	}
+}
+
+impl FromStr for Chord {
+	type Err = String;
+
+	fn from_str(chord: &str) -> Result<Self, Self::Err> {
+		let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+
+		// A trailing empty part means the key itself is "+", e.g. "Ctrl++"
+		if parts.len() > 1 && parts[parts.len() - 1].is_empty() && parts[parts.len() - 2].is_empty() {
+			parts.truncate(parts.len() - 2);
+			parts.push("+");
+		}
+
+		let (key, modifiers) = parts.split_last().ok_or_else(|| "Empty key chord".to_string())?;
+
+		let modifiers = modifiers.iter().try_fold(Modifiers::empty(), |modifiers, modifier| {
+			Ok(modifiers | match modifier.to_lowercase().as_str() {
+				"ctrl" | "control" => Modifiers::CTRL,
+				"shift" => Modifiers::SHIFT,
+				"alt" | "option" => Modifiers::ALT,
+				"super" | "cmd" | "command" | "logo" | "meta" | "win" => Modifiers::LOGO,
+				"mod" => Modifiers::COMMAND,
+				_ => return Err(format!("Unknown modifier \"{modifier}\" in \"{chord}\"")),
+			})
+		})?;
+
+		let key = if key.chars().count() == 1 {
+			Key::Character((*key).into())
+		} else {
+			Key::Named(
+				named_key(key).ok_or_else(|| format!("Unknown key \"{key}\" in \"{chord}\""))?
+			)
+		};
+
+		Ok(Chord::new(modifiers, key))
+	}
+}
+
+/// One or more chords pressed one after another, like "Ctrl+K Ctrl+C".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence(pub Vec<Chord>);
+
+impl fmt::Display for Sequence {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (index, chord) in self.0.iter().enumerate() {
+			if index > 0 {
+				write!(f, " ")?;
+			}
+
+			write!(f, "{chord}")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl FromStr for Sequence {
+	type Err = String;
+
+	fn from_str(sequence: &str) -> Result<Self, Self::Err> {
+		let chords = sequence
+			.split_whitespace()
+			.map(Chord::from_str)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		if chords.is_empty() {
+			Err("Empty key sequence".to_string())
+		} else {
+			Ok(Sequence(chords))
+		}
+	}
+}
+
+impl Sequence {
+	fn starts_with(&self, other: &[Chord]) -> bool {
+		self.0.starts_with(other)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybinding {
+	pub sequence: Sequence,
+	pub action: Action,
+}
+
+/// A new binding being recorded on the settings page.
+#[derive(Debug, Clone)]
+pub struct Recording {
+	pub action: Action,
+	pub chords: Vec<Chord>,
+}
+
+/// The outcome of the keys pressed so far.
+pub enum Resolution {
+	Action(Action),
+	/// The keys are the start of a longer sequence.
+	Pending,
+	Unbound,
+}
+
+/// The longest key sequence that can be recorded.
+pub const MAX_SEQUENCE_LENGTH: usize = 3;
+
+#[must_use]
+pub fn defaults() -> Vec<Keybinding> {
+	let command = Modifiers::COMMAND;
+	let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+
+	[
+		(Chord::character(command, "n"), Action::New),
+		(Chord::character(command, "o"), Action::Open),
+		(Chord::character(command_shift, "o"), Action::OpenFolder),
+		(Chord::character(command, "p"), Action::QuickOpen),
+		(Chord::character(command, "s"), Action::Save),
+		(Chord::character(command_shift, "s"), Action::SaveAs),
+		(Chord::character(command, "w"), Action::Close),
+		(Chord::character(command_shift, "t"), Action::ReopenClosedTab),
+		(Chord::named(Modifiers::CTRL, Named::PageDown), Action::NextTab),
+		(Chord::named(Modifiers::CTRL, Named::PageUp), Action::PreviousTab),
+		(Chord::named(Modifiers::CTRL, Named::Tab), Action::NextRecentTab),
+		(Chord::named(Modifiers::CTRL | Modifiers::SHIFT, Named::Tab), Action::PreviousRecentTab),
+		(Chord::character(command, ","), Action::Settings),
+		(Chord::character(command, "z"), Action::Undo),
+		(Chord::character(command, "y"), Action::Redo),
+		(Chord::character(command_shift, "z"), Action::Redo),
+		(Chord::character(command, "f"), Action::Find),
+		(Chord::character(command, "h"), Action::Find),
+		(Chord::named(Modifiers::empty(), Named::F3), Action::FindNext),
+		(Chord::named(Modifiers::SHIFT, Named::F3), Action::FindPrevious),
+		(Chord::character(command_shift, "f"), Action::FindInFiles),
+		(Chord::character(command, "b"), Action::ToggleSidebar),
+		(Chord::character(command, "\\"), Action::SplitRight),
+		(Chord::character(command_shift, "p"), Action::CommandPalette),
+		(Chord::named(Modifiers::empty(), Named::Escape), Action::Dismiss),
+	]
+		.into_iter()
+		.map(|(chord, action)| Keybinding {
+			sequence: Sequence(vec![chord]),
+			action,
+		})
+		.collect()
+}
+
+/// Bindings that used to be defaults until their keys were given to another
+/// action. Configurations written back then still hold them, so they give way
+/// to the current defaults unless the configuration knows the newer action.
+const REPLACED_DEFAULTS: [(&str, Action, Action); 2] = [
+	("Ctrl+Tab", Action::NextTab, Action::NextRecentTab),
+	("Ctrl+Shift+Tab", Action::PreviousTab, Action::PreviousRecentTab),
+];
+
+/// The action name that removes the default binding of a key sequence.
+const UNBOUND: &str = "none";
+
+/// Builds the keybindings from the configuration, which maps key sequences to
+/// action names, or to `none` to unbind them. Actions the configuration
+/// doesn't mention keep their default bindings, unless those keys were given
+/// to another action or unbound.
+///
+/// Returns the bindings along with a description of every invalid entry.
+pub fn load(config: &BTreeMap<String, String>) -> (Vec<Keybinding>, Vec<String>) {
+	let mut bindings: Vec<Keybinding> = Vec::new();
+	let mut unbound: Vec<Sequence> = Vec::new();
+	let mut errors = Vec::new();
+
+	let mentioned: Vec<Action> = config.values().filter_map(|name| Action::from_name(name)).collect();
+
+	for (sequence, name) in config {
+		let action = Action::from_name(name);
+
+		if action.is_none() && name != UNBOUND {
+			errors.push(format!("Unknown command \"{name}\" bound to \"{sequence}\""));
+			continue;
+		}
+
+		let sequence: Sequence = match sequence.parse() {
+			Ok(sequence) => sequence,
+			Err(error) => {
+				errors.push(error);
+				continue;
+			}
+		};
+
+		let Some(action) = action else {
+			unbound.push(sequence);
+			continue;
+		};
+
+		let replaced = REPLACED_DEFAULTS.iter().any(|(old, old_action, new_action)| {
+			*old_action == action
+				&& !mentioned.contains(new_action)
+				&& old.parse::<Sequence>().is_ok_and(|old| old == sequence)
+		});
+
+		if replaced {
+			continue;
+		}
+
+		// Different spellings, like "ctrl+s" and "Ctrl+S", are the same sequence
+		if let Some(other) = bindings.iter().find(|binding| binding.sequence == sequence) {
+			errors.push(format!(
+				"\"{sequence}\" is bound to both \"{}\" and \"{}\"",
+				other.action.name(),
+				action.name(),
+			));
+			continue;
+		}
+
+		bindings.push(Keybinding { sequence, action });
+	}
+
+	let configured: Vec<Action> = bindings.iter().map(|binding| binding.action).collect();
+
+	let defaults: Vec<Keybinding> = defaults()
+		.into_iter()
+		.filter(|binding| {
+			!configured.contains(&binding.action)
+				&& !unbound.contains(&binding.sequence)
+				&& !bindings.iter().any(|other| other.sequence == binding.sequence)
+		})
+		.collect();
+
+	bindings.extend(defaults);
+
+	(bindings, errors)
+}
+
+/// The configuration for the bindings. Default keys that aren't bound anymore
+/// are saved as `none`, so that loading doesn't bring them back.
+pub fn to_config(bindings: &[Keybinding]) -> BTreeMap<String, String> {
+	let unbound: Vec<Keybinding> = defaults()
+		.into_iter()
+		.filter(|default| !bindings.iter().any(|binding| binding.sequence == default.sequence))
+		.collect();
+
+	unbound
+		.iter()
+		.map(|binding| (binding.sequence.to_string(), UNBOUND.to_string()))
+		.chain(bindings.iter().map(|binding| (binding.sequence.to_string(), binding.action.name().to_string())))
+		.collect()
+}
+
+/// Resolves the chords pressed so far against the bindings.
+pub fn resolve(bindings: &[Keybinding], pressed: &[Chord]) -> Resolution {
+	if let Some(binding) = bindings.iter().find(|binding| binding.sequence.0 == pressed) {
+		Resolution::Action(binding.action)
+	} else if bindings.iter().any(|binding| binding.sequence.starts_with(pressed)) {
+		Resolution::Pending
+	} else {
+		Resolution::Unbound
+	}
+}
+
+/// Returns the other actions whose bindings clash with the given one, either
+/// by being the same sequence or by one being the start of the other.
+pub fn conflicts(bindings: &[Keybinding], binding: &Keybinding) -> Vec<Action> {
+	bindings
+		.iter()
+		.filter(|other| {
+			other.action != binding.action
+				&& (other.sequence.starts_with(&binding.sequence.0)
+					|| binding.sequence.starts_with(&other.sequence.0))
+		})
+		.map(|other| other.action)
+		.collect()
+}
+
+/// Returns the label of the first sequence bound to the given action.
+pub fn label(bindings: &[Keybinding], action: Action) -> Option<String> {
+	bindings
+		.iter()
+		.find(|binding| binding.action == action)
+		.map(|binding| binding.sequence.to_string())
+}
+
+fn named_key(name: &str) -> Option<Named> {
+	let named = match name.to_lowercase().as_str() {
+		"tab" => Named::Tab,
+		"enter" | "return" => Named::Enter,
+		"escape" | "esc" => Named::Escape,
+		"space" => Named::Space,
+		"backspace" => Named::Backspace,
+		"delete" | "del" => Named::Delete,
+		"insert" | "ins" => Named::Insert,
+		"home" => Named::Home,
+		"end" => Named::End,
+		"pageup" => Named::PageUp,
+		"pagedown" => Named::PageDown,
+		"arrowup" | "up" => Named::ArrowUp,
+		"arrowdown" | "down" => Named::ArrowDown,
+		"arrowleft" | "left" => Named::ArrowLeft,
+		"arrowright" | "right" => Named::ArrowRight,
+		"f1" => Named::F1,
+		"f2" => Named::F2,
+		"f3" => Named::F3,
+		"f4" => Named::F4,
+		"f5" => Named::F5,
+		"f6" => Named::F6,
+		"f7" => Named::F7,
+		"f8" => Named::F8,
+		"f9" => Named::F9,
+		"f10" => Named::F10,
+		"f11" => Named::F11,
+		"f12" => Named::F12,
+		_ => return None,
+	};
+
+	Some(named)
+}
+
+/// Characters are compared case-insensitively, since Shift changes the reported character.
+fn normalize(key: Key) -> Key {
+	match key {
+		Key::Character(character) => Key::Character(character.to_lowercase().into()),
+		key => key,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+		entries
+			.iter()
+			.map(|(sequence, action)| ((*sequence).to_string(), (*action).to_string()))
+			.collect()
+	}
+
+	fn bound(bindings: &[Keybinding], sequence: &str) -> Vec<Action> {
+		let sequence: Sequence = sequence.parse().unwrap();
+
+		bindings
+			.iter()
+			.filter(|binding| binding.sequence == sequence)
+			.map(|binding| binding.action)
+			.collect()
+	}
+
+	#[test]
+	fn replaces_the_old_tab_switching_defaults() {
+		let (bindings, errors) = load(&config(&[
+			("Ctrl+Tab", "next_tab"),
+			("Ctrl+Shift+Tab", "previous_tab"),
+		]));
+
+		assert!(errors.is_empty());
+		assert_eq!(bound(&bindings, "Ctrl+Tab"), [Action::NextRecentTab]);
+		assert_eq!(bound(&bindings, "Ctrl+Shift+Tab"), [Action::PreviousRecentTab]);
+		assert_eq!(bound(&bindings, "Ctrl+PageDown"), [Action::NextTab]);
+		assert_eq!(bound(&bindings, "Ctrl+PageUp"), [Action::PreviousTab]);
+	}
+
+	#[test]
+	fn keeps_tab_switching_bindings_chosen_after_the_change() {
+		let (bindings, _) = load(&config(&[
+			("Ctrl+Tab", "next_tab"),
+			("Alt+Tab", "next_recent_tab"),
+		]));
+
+		assert_eq!(bound(&bindings, "Ctrl+Tab"), [Action::NextTab]);
+		assert_eq!(bound(&bindings, "Alt+Tab"), [Action::NextRecentTab]);
+		assert!(bound(&bindings, "Ctrl+PageDown").is_empty());
+	}
+
+	#[test]
+	fn reports_sequences_bound_twice() {
+		let (bindings, errors) = load(&config(&[("Ctrl+J", "save"), ("ctrl+j", "undo")]));
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(bound(&bindings, "Ctrl+J").len(), 1);
+	}
+
+	#[test]
+	fn configured_keys_take_precedence_over_defaults() {
+		let (bindings, errors) = load(&config(&[("Mod+S", "find")]));
+
+		assert!(errors.is_empty());
+		assert_eq!(bound(&bindings, "Mod+S"), [Action::Find]);
+	}
+
+	#[test]
+	fn unbound_default_keys_stay_unbound() {
+		let mut bindings = defaults();
+		bindings.retain(|binding| binding.action != Action::Close);
+
+		let saved = to_config(&bindings);
+
+		assert_eq!(saved.get(&Chord::character(Modifiers::COMMAND, "w").to_string()).map(String::as_str), Some("none"));
+
+		let (loaded, errors) = load(&saved);
+
+		assert!(errors.is_empty());
+		assert!(loaded.iter().all(|binding| binding.action != Action::Close));
+	}
+
+	#[test]
+	fn parses_chords() {
+		assert_eq!("Ctrl+Shift+S".parse(), Ok(Chord::character(Modifiers::CTRL | Modifiers::SHIFT, "s")));
+		assert_eq!("ctrl + alt + pagedown".parse(), Ok(Chord::named(Modifiers::CTRL | Modifiers::ALT, Named::PageDown)));
+		assert_eq!("Mod+,".parse(), Ok(Chord::character(Modifiers::COMMAND, ",")));
+		assert_eq!("Ctrl++".parse(), Ok(Chord::character(Modifiers::CTRL, "+")));
+		assert_eq!("F3".parse(), Ok(Chord::named(Modifiers::empty(), Named::F3)));
+	}
+
+	#[test]
+	fn rejects_unknown_keys_and_modifiers() {
+		assert!("Hyper+S".parse::<Chord>().is_err());
+		assert!("Ctrl+Banana".parse::<Chord>().is_err());
+		assert!("".parse::<Chord>().is_err());
+	}
+
+	#[test]
+	fn parses_sequences() {
+		let sequence: Sequence = "Ctrl+K  Ctrl+C".parse().unwrap();
+
+		assert_eq!(
+			sequence,
+			Sequence(vec![Chord::character(Modifiers::CTRL, "k"), Chord::character(Modifiers::CTRL, "c")]),
+		);
+		assert_eq!(sequence.to_string().parse(), Ok(sequence));
+		assert!("   ".parse::<Sequence>().is_err());
+	}
+
+	#[test]
+	fn resolves_sequences_chord_by_chord() {
+		let (bindings, _) = load(&config(&[("Ctrl+K Ctrl+C", "save")]));
+
+		let first = Chord::character(Modifiers::CTRL, "k");
+		let second = Chord::character(Modifiers::CTRL, "c");
+
+		assert!(matches!(resolve(&bindings, std::slice::from_ref(&first)), Resolution::Pending));
+		assert!(matches!(resolve(&bindings, &[first, second]), Resolution::Action(Action::Save)));
+		assert!(matches!(resolve(&bindings, &[Chord::character(Modifiers::CTRL, "q")]), Resolution::Unbound));
+	}
+
+	#[test]
+	fn motions_map_to_the_keys_making_them() {
+		assert_eq!(Chord::for_motion(Modifiers::CTRL, Motion::PageDown), "Ctrl+PageDown".parse().unwrap());
+		assert_eq!(Chord::for_motion(Modifiers::CTRL, Motion::WordLeft), "Ctrl+Left".parse().unwrap());
+		assert_eq!(Chord::for_motion(Modifiers::CTRL, Motion::DocumentEnd), "Ctrl+End".parse().unwrap());
+	}
+
+	#[test]
+	fn only_plain_keys_count_as_typing() {
+		assert!(Chord::character(Modifiers::empty(), "s").is_typing());
+		assert!(Chord::character(Modifiers::SHIFT, "s").is_typing());
+		assert!(Chord::named(Modifiers::SHIFT, Named::ArrowLeft).is_typing());
+		assert!(!Chord::character(Modifiers::CTRL, "s").is_typing());
+		assert!(!Chord::named(Modifiers::CTRL, Named::PageDown).is_typing());
+		assert!(!Chord::named(Modifiers::empty(), Named::Escape).is_typing());
+		assert!(!Chord::named(Modifiers::empty(), Named::F3).is_typing());
+	}
+
+	#[test]
+	fn saved_bindings_load_back_unchanged() {
+		let (bindings, errors) = load(&to_config(&defaults()));
+
+		assert!(errors.is_empty());
+		assert_eq!(to_config(&bindings), to_config(&defaults()));
+	}
+}
@@ -2,32 +2,106 @@
 #![warn(clippy::perf, clippy::pedantic)]
 #![deny(rust_2024_compatibility)]
 
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::ffi::OsStr;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime};
 
-use iced::highlighter::Highlighter;
 use iced::widget::combo_box::State;
-use iced::widget::{container, horizontal_space, row, text, text_editor, Column, Row};
+use iced::widget::{
+	container, horizontal_space, pane_grid, pick_list, row, scrollable, text, text_editor, text_input, Column,
+	PaneGrid, Row,
+};
 use iced::window::settings::PlatformSpecific;
 use iced::window::{icon, Level, Position};
 use iced::{
-	executor, highlighter, window, Alignment, Application, Command, Element, Font, Length, Pixels,
-	Settings, Size, Theme,
+	event, executor, highlighter, keyboard, mouse, window, Alignment, Application, Command, Element, Event, Font,
+	Length, Pixels, Settings, Size, Subscription, Theme,
 };
 use iced_aw::menu::{Item, Menu};
 use iced_aw::{menu, menu_bar, menu_items, Modal};
 use serde::{Deserialize, Serialize};
 
 mod editor;
+mod atomic_write;
+mod autosave;
+mod cli;
 mod config;
+mod encoding;
+mod find_in_files;
+mod format;
+mod history;
+// Handing files to the running editor needs Unix domain sockets, so on other
+// platforms every launch opens its own window
+#[cfg(unix)]
+mod instance;
+mod keybindings;
+mod palette;
+mod panes;
+mod quick_open;
+mod search;
+mod session;
+mod tab_switcher;
+mod watcher;
+mod workspace;
+
+use atomic_write::Backup;
+use autosave::{Leftovers, Recovery, Swap};
+use encoding::Encoding;
+use find_in_files::FindInFiles;
+use format::{LineEnding, TextFormat};
+use history::History;
+use keybindings::{Action, Chord, Keybinding, Recording, Resolution, Sequence};
+use palette::Palette;
+use panes::{Pane, View};
+use quick_open::QuickOpen;
+use search::{LineIndex, Search};
+use session::Session;
+use tab_switcher::TabSwitcher;
+use watcher::{Conflict, DiskState};
+use workspace::{Operation, Prompt, PromptKind, Workspace};
 
 pub static JETBRAINS_MONO: LazyLock<Font> = LazyLock::new(|| Font::with_name("JetBrains Mono"));
 
 pub static INTER: LazyLock<Font> = LazyLock::new(|| Font::with_name("Inter"));
 
 fn main() -> iced::Result {
+	#[cfg_attr(not(unix), allow(unused_mut))]
+	let mut args = match cli::parse(std::env::args().skip(1)) {
+		Ok(args) => args,
+		Err(error) => {
+			eprintln!("{error}\n\n{}", cli::USAGE);
+			std::process::exit(2);
+		}
+	};
+
+	if args.help {
+		println!("{}", cli::USAGE);
+		return Ok(());
+	}
+
+	for error in &args.errors {
+		eprintln!("{error}");
+	}
+
+	#[cfg(unix)]
+	if !args.new_window {
+		if !instance::is_running() {
+			args.single_instance = true;
+		} else if args.only_files() && instance::forward(&instance::Request::new(&args.files)) {
+			return Ok(());
+		}
+	}
+
+	if let Some(path) = &args.config {
+		config::CONFIG_OVERRIDE
+			.set(path.clone())
+			.expect("The configuration path is only set once");
+	}
+
 	Editor::run(Settings {
 		id: None,
 		window: window::Settings {
@@ -42,9 +116,9 @@ fn main() -> iced::Result {
 			level: Level::default(),
 			icon: Some(icon::from_file_data(include_bytes!("../assets/icon.png"), None).unwrap()),
 			platform_specific: PlatformSpecific::default(),
-			exit_on_close_request: true,
+			exit_on_close_request: false,
 		},
-		flags: Default::default(),
+		flags: args,
 		fonts: vec![
 			include_bytes!("../assets/bootstrap-icons.ttf")
 				.as_slice()
@@ -62,48 +136,227 @@ fn main() -> iced::Result {
 	})
 }
 
+#[allow(clippy::struct_excessive_bools)]
 struct Editor {
 	files: Vec<File>,
+	/// The index of the file shown in the focused pane.
 	current: usize,
+	panes: pane_grid::State<Pane>,
+	focus: pane_grid::Pane,
+	/// Recently closed tabs, the most recently closed last.
+	closed_tabs: VecDeque<ClosedTab>,
+	/// Folders dropped onto the window, waiting for the dialog asking how to
+	/// open them until no other dialog is shown.
+	dropped_folders: VecDeque<PathBuf>,
+	/// Dropped files still being opened. Failures are collected until all of
+	/// them are done, and a new drop starts a new list.
+	pending_drops: usize,
+	/// The tab being dragged along the tab strip of its pane.
+	dragged_tab: Option<(pane_grid::Pane, usize)>,
 	error: Option<Error>,
 	modal_shown: bool,
 	modal_type: ModalType,
 	theme: Theme,
+	/// The theme kept in the configuration, which a theme given on the
+	/// command line only replaces for this window.
+	configured_theme: Theme,
 	themes: State<Theme>,
 	highlighter_theme: highlighter::Theme,
 	highlighter_themes: State<highlighter::Theme>,
+	history_limit: usize,
+	backup: Backup,
+	recent_files: Vec<PathBuf>,
+	/// Recent files that were moved or deleted, which stay listed but can't be
+	/// opened. Checked when the window gets focus rather than on every render.
+	missing_files: BTreeSet<PathBuf>,
+	autosave: autosave::Mode,
+	/// Seconds to wait after the last edit before autosaving a file.
+	autosave_delay: u64,
+	recovery: Recovery,
+	/// Swap files of a previous run that crashed, waiting to be recovered.
+	leftovers: Leftovers,
+	search: Search,
+	find_in_files: FindInFiles,
+	keybindings: Vec<Keybinding>,
+	keybinding_errors: Vec<String>,
+	pending_chords: Vec<Chord>,
+	recording: Option<Recording>,
+	settings_page: SettingsPage,
+	palette: Palette,
+	quick_open: QuickOpen,
+	tab_switcher: TabSwitcher,
+	/// The modifier keys held down, since switching tabs finishes once they
+	/// are released.
+	modifiers: keyboard::Modifiers,
+	workspace: Option<Workspace>,
+	show_sidebar: bool,
+	/// The session as it was last written to disk.
+	session: Option<Session>,
+	/// Whether anything happened since the session was last captured.
+	session_changed: bool,
+	/// Whether the session is restored and saved, which a window opened with
+	/// `--new-window` doesn't do.
+	persist_session: bool,
+	/// Whether files opened by other launches of the editor are received here.
+	single_instance: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsState {
 	theme: String,
 	syntax_theme: String,
+	#[serde(default = "history::default_limit")]
+	history_limit: usize,
+	/// Maps key sequences, like "Ctrl+K Ctrl+C", to the names of actions.
+	#[serde(default)]
+	keybindings: BTreeMap<String, String>,
+	#[serde(default)]
+	recent_commands: Vec<String>,
+	#[serde(default)]
+	backup: Backup,
+	/// Recently opened and saved files, the most recent first.
+	#[serde(default)]
+	recent_files: Vec<PathBuf>,
+	#[serde(default)]
+	autosave: autosave::Mode,
+	#[serde(default = "autosave::default_delay")]
+	autosave_delay: u64,
 }
 
 pub struct File {
+	id: FileId,
 	path: Option<PathBuf>,
 	content: text_editor::Content,
+	/// Counts the changes to the text, so that what's found in it is only
+	/// looked for again once it changes.
+	revision: u64,
 	is_modified: bool,
+	format: TextFormat,
+	history: History,
+	read_only: bool,
+	/// When the file was last modified on disk as far as the tab knows, to
+	/// notice changes made by other programs.
+	modified_at: Option<SystemTime>,
+	conflict: Option<Conflict>,
+	/// When the text was last changed and not autosaved since.
+	edited_at: Option<Instant>,
+	/// The swap file holding the unsaved changes of the tab.
+	swap: Option<PathBuf>,
+	/// When the tab was last the current one, to switch between recently
+	/// used tabs.
+	activated_at: Option<Instant>,
+	/// Pinned tabs stay at the start of the tab strip and aren't closed along
+	/// with other tabs.
+	pinned: bool,
+}
+
+/// Identifies a tab for commands that finish after tabs may have been closed
+/// or moved, which shifts their indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(u64);
+
+impl FileId {
+	fn unique() -> Self {
+		static NEXT: AtomicU64 = AtomicU64::new(0);
+
+		FileId(NEXT.fetch_add(1, Ordering::Relaxed))
+	}
 }
 
 impl File {
 	fn empty() -> Self {
 		File {
+			id: FileId::unique(),
 			path: None,
 			content: text_editor::Content::new(),
+			revision: 0,
 			is_modified: false,
+			format: TextFormat::default(),
+			history: History::default(),
+			read_only: false,
+			modified_at: None,
+			conflict: None,
+			edited_at: None,
+			swap: None,
+			activated_at: None,
+			pinned: false,
+		}
+	}
+
+	/// Updates the tab after its text was written to the given path.
+	fn saved(&mut self, path: PathBuf) {
+		self.modified_at = watcher::modified_time(&path);
+		self.conflict = None;
+		self.path = Some(path);
+		self.is_modified = false;
+		self.history.mark_clean();
+	}
+
+	fn name(&self) -> &str {
+		match &self.path {
+			None => "New file",
+			Some(path) => path
+				.file_name()
+				.and_then(OsStr::to_str)
+				.unwrap_or("Error"),
 		}
 	}
 
+	/// Reopens a tab of a previous session, returning `None` if its file can't
+	/// be read anymore.
+	fn restore(tab: session::Tab) -> Option<Self> {
+		let (text, format, is_modified) = match (tab.unsaved, &tab.path) {
+			(Some(text), _) => (text, tab.format, true),
+			(None, Some(path)) => {
+				let (text, format) = decode_file(&std::fs::read(path).ok()?, None);
+
+				(text, format, false)
+			}
+			(None, None) => (String::new(), tab.format, false),
+		};
+
+		Some(File {
+			id: FileId::unique(),
+			modified_at: tab.path.as_deref().and_then(watcher::modified_time),
+			path: tab.path,
+			content: content_with_cursor(&text, tab.cursor),
+			revision: 0,
+			is_modified,
+			format,
+			history: if is_modified {
+				History::unsaved()
+			} else {
+				History::default()
+			},
+			read_only: tab.read_only,
+			conflict: None,
+			edited_at: None,
+			swap: None,
+			activated_at: None,
+			pinned: tab.pinned,
+		})
+	}
+
 	fn sample() -> Self {
 		let sample = include_bytes!("../src/main.rs").as_slice();
 
-		let content = verify_content(String::from_utf8_lossy(sample).to_string()).leak();
+		let (content, format) = verify_content(String::from_utf8_lossy(sample).to_string());
 
 		File {
+			id: FileId::unique(),
 			path: None,
-			content: text_editor::Content::with_text(content),
+			content: text_editor::Content::with_text(&content),
+			revision: 0,
 			is_modified: true,
+			format,
+			history: History::unsaved(),
+			read_only: false,
+			modified_at: None,
+			conflict: None,
+			edited_at: None,
+			swap: None,
+			activated_at: None,
+			pinned: false,
 		}
 	}
 }
@@ -111,21 +364,139 @@ impl File {
 #[derive(Debug, Clone)]
 enum Message {
 	Edit(text_editor::Action),
+	Undo,
+	Redo,
 	New,
 	Open,
-	FileOpened(Result<(PathBuf, Arc<String>), Error>),
+	FileOpened(Result<(PathBuf, Arc<String>, TextFormat), Error>),
+	FileReloaded(Result<(PathBuf, Arc<String>, TextFormat), Error>),
 	Save,
 	SaveAs,
-	FileSaved(Result<PathBuf, Error>),
+	FileSaved(FileId, Result<PathBuf, Error>),
 	Close,
 	CloseIndex(usize),
-	SelectFile(usize),
+	ReopenClosedTab,
+	ClosedTabLoaded(ClosedTab, Result<(PathBuf, Arc<String>, TextFormat), Error>),
+	SaveAndClose(FileId),
+	SavedBeforeClose(FileId, Result<PathBuf, Error>),
+	DiscardAndClose(FileId),
+	CloseMany(CloseScope),
+	CloseTabs(Vec<FileId>),
+	CloseRequested,
+	SaveAllAndClose(CloseRequest),
+	SavedBeforeClosing(CloseRequest, FileId, Result<PathBuf, Error>),
+	DiscardAllAndClose(CloseRequest),
+	SelectTab(pane_grid::Pane, usize),
+	CloseTab(pane_grid::Pane, usize),
+	StartTabDrag(pane_grid::Pane, usize),
+	TabHovered(pane_grid::Pane, usize),
+	EndTabDrag,
+	TogglePin(usize),
+	ScrollTabs(pane_grid::Pane, f32),
+	TabsScrolled(pane_grid::Pane, scrollable::Viewport),
+	EditIn(pane_grid::Pane, text_editor::Action),
+	FocusPane(pane_grid::Pane),
+	SplitPane(pane_grid::Axis),
+	ClosePane(pane_grid::Pane),
+	PaneResized(pane_grid::ResizeEvent),
+	SelectNextFile,
+	SelectPreviousFile,
+	NextRecentTab,
+	PreviousRecentTab,
+	SwitchToTab(usize),
+	ModifiersChanged(keyboard::Modifiers),
+	KeyPressed(keyboard::Key, keyboard::Modifiers, event::Status),
+	Dismiss,
+	SetLineEnding(LineEnding),
+	ReopenWithEncoding(Encoding),
+	SaveAndReopen(FileId, Encoding),
+	SavedBeforeReopen(FileId, Encoding, Result<PathBuf, Error>),
+	ReloadWithEncoding(FileId, Encoding),
+	SaveWithEncoding(Encoding),
+	SavedWithEncoding(FileId, TextFormat, Result<PathBuf, Error>),
+	SetBom(bool),
 	OpenURL(&'static str),
 	ShowInExplorer(PathBuf),
 	ShowModal(ModalType),
 	HideModal,
 	SelectTheme(Theme),
 	SelectSyntaxTheme(highlighter::Theme),
+	SetHistoryLimit(usize),
+	SetBackup(Backup),
+	OpenPath(PathBuf),
+	ClearRecentFiles,
+	OpenFolder,
+	FolderPicked(Option<PathBuf>),
+	OpenWorkspace(PathBuf),
+	FileDropped(PathBuf),
+	DroppedFileOpened(PathBuf, Result<(PathBuf, Arc<String>, TextFormat), Error>),
+	OpenFilesIn(PathBuf),
+	FilesListed(PathBuf, Result<Vec<workspace::Entry>, Error>),
+	CloseFolder,
+	ToggleSidebar,
+	RefreshWorkspace,
+	ToggleDirectory(PathBuf),
+	DirectoryRead(PathBuf, Result<Vec<workspace::Entry>, Error>),
+	OpenEntry(PathBuf),
+	ShowPrompt(PromptKind),
+	PromptChanged(String),
+	SubmitPrompt,
+	ApplyOperation(Operation),
+	OperationFinished(Result<Operation, Error>),
+	SetAutosave(autosave::Mode),
+	SetAutosaveDelay(u64),
+	Autosave,
+	AutoSaved(FileId, String, Result<PathBuf, Error>),
+	RecoverSwaps,
+	DiscardSwaps,
+	ShowSettingsPage(SettingsPage),
+	StartRecording(Action),
+	Unbind(Action),
+	ApplyRecording,
+	CancelRecording,
+	ResetKeybindings,
+	ShowCommandPalette,
+	PaletteQueryChanged(String),
+	RunCommand(usize),
+	ShowQuickOpen,
+	QuickOpenQueryChanged(String),
+	OpenQuickOpenEntry(PathBuf),
+	WorkspaceIndexed(PathBuf, Vec<PathBuf>),
+	SaveSession,
+	FocusWindow,
+	WindowFocused,
+	FilesChanged(Vec<PathBuf>),
+	DiskStateRead(PathBuf, Option<DiskState>),
+	ReloadFromDisk(usize),
+	KeepMine(usize),
+	Overwrite(FileId, Option<CloseRequest>),
+	ShowSearch,
+	HideSearch,
+	SearchQueryChanged(String),
+	ReplacementChanged(String),
+	SetSearchOptions(search::Options),
+	FindNext,
+	FindPrevious,
+	ReplaceOne,
+	ReplaceAll,
+	ShowFindInFiles,
+	HideFindInFiles,
+	FindInFilesQueryChanged(String),
+	FindInFilesReplacementChanged(String),
+	SetFindInFilesOptions(search::Options),
+	SetFindInFilesScope(find_in_files::Scope),
+	PickSearchDirectory,
+	SearchDirectoryPicked(Option<PathBuf>),
+	IncludeChanged(String),
+	ExcludeChanged(String),
+	RunFindInFiles,
+	FindInFilesFinished(Result<Vec<find_in_files::FileResults>, Error>),
+	OpenSearchResult(find_in_files::Location, (usize, usize)),
+	ReplaceInFiles,
+	ApplyReplaceInFiles,
+	ReplacedInFiles(Result<usize, Error>),
+	OpenAt(PathBuf, (usize, usize)),
+	FileOpenedAt(Result<(PathBuf, Arc<String>, TextFormat), Error>, (usize, usize)),
 	None,
 }
 
@@ -133,8 +504,62 @@ enum Message {
 enum ModalType {
 	About,
 	Settings,
+	Encoding,
+	UnsavedChanges(FileId),
+	UnsavedChangesBeforeReopen(FileId, Encoding),
+	UnsavedChangesMany(CloseRequest),
+	CommandPalette,
+	QuickOpen,
+	TabSwitcher,
+	/// A save that was refused because the file changed on disk, and the
+	/// tabs that were waiting for it to be closed.
+	ChangedOnDisk(FileId, Option<CloseRequest>),
+	Compare(usize),
+	Recovery,
+	/// A path to delete, and whether it's a folder.
+	ConfirmDelete(PathBuf, bool),
+	DroppedFolder(PathBuf),
+	/// Asks before Replace All rewrites files that aren't open, with the
+	/// amount of them.
+	ConfirmReplaceInFiles(usize),
+	/// Tabs showing files that were deleted from the sidebar.
+	DeletedFiles(Vec<FileId>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsPage {
+	General,
+	Keybindings,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CloseScope {
+	Others(usize),
+	ToTheRight(pane_grid::Pane, usize),
+	Saved,
+	All,
+}
+
+/// A set of tabs waiting to be closed once their unsaved changes are dealt with.
+#[derive(Debug, Clone)]
+enum CloseRequest {
+	Tabs(Vec<FileId>),
+	Window,
+}
+
+/// A closed tab, along with where it was so it can be reopened there.
+#[derive(Debug, Clone)]
+struct ClosedTab {
+	pane: pane_grid::Pane,
+	/// The position of the tab in the pane's tab strip.
+	position: usize,
+	tab: session::Tab,
 }
 
+const MAX_RECENT_FILES: usize = 10;
+
+const MAX_CLOSED_TABS: usize = 20;
+
 pub const THEMES: [Theme; 21] = [
 	Theme::Light,
 	Theme::Dark,
@@ -163,91 +588,289 @@ impl Application for Editor {
 	type Executor = executor::Default;
 	type Message = Message;
 	type Theme = Theme;
-	type Flags = ();
+	type Flags = cli::Args;
 
-	fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
-		let (theme, syntax) = if let Some(config) = &*config::CONFIG {
+	#[allow(clippy::too_many_lines)]
+	fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+		let (theme, syntax, history_limit, backup) = if let Some(config) = &*config::CONFIG {
 			(
 				config::key_to_theme(&config.theme),
 				config::key_to_syntax_theme(&config.syntax_theme),
+				config.history_limit,
+				config.backup,
 			)
 		} else {
-			(Theme::Dark, highlighter::Theme::Base16Eighties)
+			(Theme::Dark, highlighter::Theme::Base16Eighties, history::DEFAULT_LIMIT, Backup::Off)
+		};
+
+		let (autosave, autosave_delay) = config::CONFIG
+			.as_ref()
+			.map_or((autosave::Mode::Off, autosave::DEFAULT_DELAY), |config| {
+				(config.autosave, config.autosave_delay)
+			});
+
+		// Looked for before this editor's own recovery folder exists
+		let leftovers = Leftovers::find();
+
+		if leftovers.swaps.is_empty() {
+			leftovers.remove();
+		}
+
+		let recent_files = config::CONFIG
+			.as_ref()
+			.map(|config| config.recent_files.clone())
+			.unwrap_or_default();
+
+		let configured_theme = theme.clone();
+		let theme = flags.theme.clone().unwrap_or(theme);
+
+		let session = if flags.new_window {
+			None
+		} else {
+			session::load()
+		};
+
+		let workspace = session
+			.as_ref()
+			.and_then(|session| session.workspace.clone())
+			.filter(|root| root.is_dir())
+			.map(Workspace::new);
+
+		let command = workspace.as_ref().map_or_else(Command::none, |workspace| {
+			Command::batch([read_directory(workspace.root.clone()), index_workspace(workspace.root.clone())])
+		});
+
+		let (files, current, restored_panes, errors) = startup_files(&flags, session);
+
+		let mut restored_panes = restored_panes.into_iter();
+		let first = restored_panes.next().unwrap_or_else(|| Pane::new(vec![current], current));
+		let (mut panes, focus) = pane_grid::State::new(first);
+		let mut last = focus;
+
+		for pane in restored_panes {
+			if let Some((pane, _)) = panes.split(pane_grid::Axis::Vertical, last, pane) {
+				last = pane;
+			}
+		}
+
+		let (keybindings, keybinding_errors) = match &*config::CONFIG {
+			Some(config) => keybindings::load(&config.keybindings),
+			None => (keybindings::defaults(), Vec::new()),
 		};
 
 		(
 			Self {
-				files: vec![File::sample()],
-				error: None,
-				current: 0,
-				modal_shown: false,
-				modal_type: ModalType::About,
+				files,
+				error: errors
+					.first()
+					.map(|error| Error::InvalidArgument(error.clone()))
+					.or_else(|| {
+						keybinding_errors
+							.first()
+							.map(|error| Error::InvalidKeybinding(error.clone()))
+					}),
+				current,
+				panes,
+				focus,
+				closed_tabs: VecDeque::new(),
+				dropped_folders: VecDeque::new(),
+				pending_drops: 0,
+				dragged_tab: None,
+				modal_shown: !leftovers.swaps.is_empty(),
+				modal_type: ModalType::Recovery,
 				theme,
+				configured_theme,
 				themes: State::new(THEMES.to_vec()),
 				highlighter_theme: syntax,
 				highlighter_themes: State::new(highlighter::Theme::ALL.to_vec()),
+				history_limit,
+				backup,
+				missing_files: missing_files(&recent_files),
+				recent_files,
+				autosave,
+				autosave_delay,
+				recovery: Recovery::new(),
+				leftovers,
+				search: Search::default(),
+				find_in_files: FindInFiles::default(),
+				keybindings,
+				keybinding_errors,
+				pending_chords: Vec::new(),
+				recording: None,
+				settings_page: SettingsPage::General,
+				palette: Palette {
+					recent: config::CONFIG
+						.as_ref()
+						.map(|config| config.recent_commands.clone())
+						.unwrap_or_default(),
+					..Palette::default()
+				},
+				quick_open: QuickOpen::default(),
+				tab_switcher: TabSwitcher::default(),
+				modifiers: keyboard::Modifiers::empty(),
+				show_sidebar: workspace.is_some(),
+				workspace,
+				session: None,
+				session_changed: false,
+				persist_session: !flags.new_window,
+				single_instance: flags.single_instance,
 			},
-			Command::none(),
+			command,
 		)
 	}
 
 	fn title(&self) -> String {
+		let file = &self.files[self.current];
+
+		let name = if file.path.is_some() {
+			self.tab_name(self.current)
+		} else {
+			String::from("New File")
+		};
+
 		format!(
-			"Multi Tab Text Editor | {}",
-			match &self.files[self.current].path {
-				None => format!(
-					"New File{}",
-					if self.files[self.current].is_modified {
-						"*"
-					} else {
-						""
-					}
-				),
-				Some(path) => {
-					let mut name = path.file_name()
-						.unwrap_or(OsStr::new(""))
-						.to_str()
-						.unwrap_or("")
-						.to_string();
-					
-					if self.files[self.current].is_modified {
-						name.push('*');
-					}
-					
-					name
-				}
-			}
+			"Multi Tab Text Editor | {name}{}",
+			if file.is_modified { "*" } else { "" }
 		)
 	}
 
 	#[allow(clippy::too_many_lines)]
 	fn update(&mut self, message: Message) -> Command<Message> {
-		match message {
+		let clicked = matches!(message, Message::StartTabDrag(..));
+		let (focus, current) = (self.focus, self.current);
+
+		// The timers alone don't change anything the session holds
+		if !matches!(message, Message::SaveSession | Message::Autosave) {
+			self.session_changed = true;
+		}
+
+		// Arms that are done early break out of the block rather than return,
+		// so that every message gets the panes brought in sync afterwards
+		let command = 'handled: { match message {
 			Message::Edit(action) => {
 				assert!(self.current < self.files.len());
-				
-				self.files[self.current].is_modified =
-					self.files[self.current].is_modified || action.is_edit();
+
+				// The editor moves the cursor even for keys that are bound, like
+				// Ctrl+PageDown switching tabs, which then would do two things
+				if let text_editor::Action::Move(motion) | text_editor::Action::Select(motion) = &action {
+					let chord = Chord::for_motion(self.modifiers, *motion);
+					let pressed = [self.pending_chords.as_slice(), std::slice::from_ref(&chord)].concat();
+
+					if !chord.is_typing()
+						&& !matches!(keybindings::resolve(&self.keybindings, &pressed), Resolution::Unbound)
+					{
+						break 'handled Command::none();
+					}
+				}
+
+				let file = &mut self.files[self.current];
+				let is_edit = action.is_edit();
+
+				if is_edit && file.read_only {
+					break 'handled Command::none();
+				}
+
+				file.history.record(&file.content, &action, self.history_limit);
 				self.error = None;
 
-				self.files[self.current].content.perform(action);
+				file.content.perform(action);
+
+				if is_edit {
+					file.revision += 1;
+					file.is_modified = !file.history.is_clean();
+					file.edited_at = Some(Instant::now());
+				}
+
+				Command::none()
+			}
+			Message::Undo | Message::Redo => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				if file.read_only {
+					break 'handled Command::none();
+				}
+
+				let snapshot = if matches!(message, Message::Undo) {
+					file.history.undo(&file.content)
+				} else {
+					file.history.redo(&file.content)
+				};
+
+				if let Some(snapshot) = snapshot {
+					file.content = content_with_cursor(&snapshot.text, snapshot.cursor);
+					file.revision += 1;
+					file.is_modified = !file.history.is_clean();
+					file.edited_at = Some(Instant::now());
+				}
 
 				Command::none()
 			}
 			Message::Open => Command::perform(pick_file(), Message::FileOpened),
-			Message::FileOpened(Ok((path, content))) => {
+			Message::FileOpened(Ok((path, content, format))) => {
 				assert!(self.current < self.files.len());
 
+				self.remember_file(&path);
+
+				if let Some(index) = self.files.iter().position(|file| file.path.as_ref() == Some(&path)) {
+					self.current = index;
+
+					break 'handled Command::none();
+				}
+
 				self.files.push(File::empty());
 
 				self.current = self.files.len() - 1;
 
+				self.files[self.current].modified_at = watcher::modified_time(&path);
 				self.files[self.current].path = Some(path);
 				self.files[self.current].content = text_editor::Content::with_text(&content);
+				self.files[self.current].format = format;
+
+				Command::none()
+			}
+			Message::FileReloaded(Ok((path, content, format))) => {
+				if let Some(file) = self.files
+					.iter_mut()
+					.find(|file| file.path.as_ref() == Some(&path))
+				{
+					file.content = text_editor::Content::with_text(&content);
+					file.revision += 1;
+					file.format = format;
+					file.is_modified = false;
+					file.history = History::default();
+					file.modified_at = watcher::modified_time(&path);
+					file.conflict = None;
+				}
 
 				Command::none()
 			}
-			Message::FileOpened(Err(error)) | Message::FileSaved(Err(error)) => {
+			// The watcher shows the conflict once it notices the change
+			Message::AutoSaved(_, _, Err(Error::ChangedOnDisk))
+			| Message::DiskStateRead(_, None)
+			| Message::FolderPicked(None)
+			| Message::None => Command::none(),
+			Message::FileSaved(id, Err(Error::ChangedOnDisk))
+			| Message::SavedBeforeReopen(id, _, Err(Error::ChangedOnDisk)) => {
+				self.update(Message::ShowModal(ModalType::ChangedOnDisk(id, None)))
+			}
+			Message::SavedBeforeClose(id, Err(Error::ChangedOnDisk)) => self.update(Message::ShowModal(
+				ModalType::ChangedOnDisk(id, Some(CloseRequest::Tabs(vec![id]))),
+			)),
+			Message::SavedBeforeClosing(request, id, Err(Error::ChangedOnDisk)) => {
+				self.update(Message::ShowModal(ModalType::ChangedOnDisk(id, Some(request))))
+			}
+			Message::FileOpened(Err(error))
+			| Message::FileReloaded(Err(error))
+			| Message::FileSaved(_, Err(error))
+			| Message::SavedBeforeReopen(_, _, Err(error))
+			| Message::SavedWithEncoding(_, _, Err(error))
+			| Message::SavedBeforeClose(_, Err(error))
+			| Message::SavedBeforeClosing(_, _, Err(error))
+			| Message::AutoSaved(_, _, Err(error))
+			| Message::DirectoryRead(_, Err(error))
+			| Message::OperationFinished(Err(error)) => {
 				self.error = Some(error);
 
 				Command::none()
@@ -262,309 +885,2480 @@ impl Application for Editor {
 			Message::Save => {
 				assert!(self.current < self.files.len());
 
-				let text = self.files[self.current].content.text();
+				let file = &self.files[self.current];
+				let text = buffer_text(&file.content);
+				let id = file.id;
 
 				Command::perform(
-					save_file(self.files[self.current].path.clone(), text),
-					Message::FileSaved,
+					save_file(file.path.clone(), text, file.format, file.modified_at, self.backup),
+					move |result| Message::FileSaved(id, result),
 				)
 			}
 			Message::SaveAs => {
 				assert!(self.current < self.files.len());
 
-				let text = self.files[self.current].content.text();
+				let file = &self.files[self.current];
+				let text = buffer_text(&file.content);
+				let id = file.id;
 
-				Command::perform(save_file(None, text), Message::FileSaved)
+				Command::perform(
+					save_file(None, text, file.format, None, self.backup),
+					move |result| Message::FileSaved(id, result),
+				)
 			}
-			Message::FileSaved(Ok(path)) => {
-				assert!(self.current < self.files.len());
+			Message::FileSaved(id, Ok(path)) => {
+				self.remember_file(&path);
 
-				self.files[self.current].path = Some(path);
-				self.files[self.current].is_modified = false;
+				if let Some(index) = self.file_index(id) {
+					self.files[index].saved(path);
+				}
 
 				Command::none()
 			}
-			Message::Close => {
-				assert!(self.current < self.files.len());
-
-				let mut should_remove = true;
-				let remove: usize = self.current;
+			Message::Close => self.update(Message::CloseIndex(self.current)),
+			Message::CloseIndex(index) => {
+				assert!(index < self.files.len());
 
-				if self.current != 0 {
-					self.current = 0;
-				} else if self.files.len() == 1 {
-					should_remove = false;
+				if self.files[index].is_modified {
+					self.modal_shown = true;
+					self.modal_type = ModalType::UnsavedChanges(self.files[index].id);
 				} else {
-					self.current = self.files.len() - 2;
+					self.close_file(index);
 				}
 
-				if should_remove {
-					self.files.remove(remove);
-				} else {
-					self.files[self.current] = File::empty();
+				Command::none()
+			}
+			Message::ReopenClosedTab => {
+				let Some(closed) = self.closed_tabs.pop_back() else {
+					break 'handled Command::none();
+				};
+
+				// The file may have been opened again since it was closed
+				if self.focus_open_file(closed.tab.path.as_deref()) {
+					break 'handled Command::none();
+				}
+
+				// Tabs with unsaved changes are reopened with their text
+				if let (Some(path), None) = (&closed.tab.path, &closed.tab.unsaved) {
+					let path = path.clone();
+					let encoding = closed.tab.format.encoding;
+
+					break 'handled Command::perform(
+						async move { load_file_with_encoding(&path, Some(encoding)).await },
+						move |result| Message::ClosedTabLoaded(closed, result),
+					);
+				}
+
+				if let Some(file) = File::restore(closed.tab.clone()) {
+					self.reopen_tab(&closed, file);
 				}
 
 				Command::none()
 			}
-			Message::CloseIndex(index) => {
-				assert!(self.current < self.files.len());
+			Message::ClosedTabLoaded(closed, Ok((path, content, format))) => {
+				if self.focus_open_file(Some(&path)) {
+					break 'handled Command::none();
+				}
 
-				let mut should_remove = true;
+				let file = File {
+					modified_at: watcher::modified_time(&path),
+					path: Some(path),
+					content: content_with_cursor(&content, closed.tab.cursor),
+					format,
+					read_only: closed.tab.read_only,
+					pinned: closed.tab.pinned,
+					..File::empty()
+				};
 
-				if self.current != index {
-					self.current = 0;
-				} else if self.files.len() == 1 {
-					should_remove = false;
+				self.reopen_tab(&closed, file);
+
+				Command::none()
+			}
+			Message::ClosedTabLoaded(closed, Err(error)) => {
+				if let Some(path) = &closed.tab.path {
+					self.report_open_error(path, &error);
+				}
+
+				Command::none()
+			}
+			Message::SaveAndClose(id) => {
+				self.modal_shown = false;
+
+				let Some(file) = self.file(id) else {
+					break 'handled Command::none();
+				};
+
+				Command::perform(
+					save_file(file.path.clone(), buffer_text(&file.content), file.format, file.modified_at, self.backup),
+					move |result| Message::SavedBeforeClose(id, result),
+				)
+			}
+			Message::SavedBeforeClose(id, Ok(path)) => {
+				// The tab may have been closed or moved while it was being saved
+				if let Some(index) = self.file_index(id) {
+					self.files[index].saved(path);
+
+					self.close_file(index);
+				}
+
+				Command::none()
+			}
+			Message::DiscardAndClose(id) => {
+				self.modal_shown = false;
+
+				if let Some(index) = self.file_index(id) {
+					self.close_file(index);
+				}
+
+				Command::none()
+			}
+			Message::CloseMany(scope) => {
+				let indices: Vec<usize> = match scope {
+					CloseScope::ToTheRight(pane, kept) => self.panes
+						.get(pane)
+						.map(|state| state.tabs.iter().skip_while(|&&tab| tab != kept).skip(1).copied().collect())
+						.unwrap_or_default(),
+					_ => (0..self.files.len()).collect(),
+				};
+
+				// Pinned tabs are only closed one at a time
+				let ids: Vec<FileId> = indices
+					.into_iter()
+					.filter(|&index| !self.files[index].pinned)
+					.filter(|&index| match scope {
+						CloseScope::Others(kept) => index != kept,
+						CloseScope::Saved => !self.files[index].is_modified,
+						CloseScope::ToTheRight(..) | CloseScope::All => true,
+					})
+					.map(|index| self.files[index].id)
+					.collect();
+
+				self.update(Message::CloseTabs(ids))
+			}
+			Message::CloseTabs(ids) => {
+				let request = CloseRequest::Tabs(ids);
+				let indices = self.close_request_indices(&request);
+
+				if indices.iter().any(|&index| self.files[index].is_modified) {
+					self.modal_shown = true;
+					self.modal_type = ModalType::UnsavedChangesMany(request);
+				} else {
+					self.modal_shown = false;
+
+					self.close_files(&indices);
+				}
+
+				Command::none()
+			}
+			Message::CloseRequested => {
+				if self.files.iter().any(|file| file.is_modified) {
+					self.modal_shown = true;
+					self.modal_type = ModalType::UnsavedChangesMany(CloseRequest::Window);
+
+					Command::none()
 				} else {
-					self.current = self.files.len() - 2;
+					self.exit()
 				}
+			}
+			Message::SaveAllAndClose(request) => {
+				self.modal_shown = false;
+
+				let dirty = self.close_request_indices(&request)
+					.into_iter()
+					.find(|&index| self.files[index].is_modified);
 
-				if should_remove {
-					self.files.remove(index);
+				if let Some(index) = dirty {
+					let file = &self.files[index];
+					let id = file.id;
+
+					Command::perform(
+						save_file(file.path.clone(), buffer_text(&file.content), file.format, file.modified_at, self.backup),
+						move |result| Message::SavedBeforeClosing(request, id, result),
+					)
 				} else {
-					self.files[self.current] = File::empty();
+					self.update(Message::DiscardAllAndClose(request))
+				}
+			}
+			Message::SavedBeforeClosing(request, id, Ok(path)) => {
+				if let Some(index) = self.file_index(id) {
+					self.files[index].saved(path);
+				}
+
+				self.update(Message::SaveAllAndClose(request))
+			}
+			Message::DiscardAllAndClose(request) => {
+				self.modal_shown = false;
+
+				match request {
+					CloseRequest::Tabs(_) => {
+						self.close_files(&self.close_request_indices(&request));
+
+						Command::none()
+					}
+					CloseRequest::Window => {
+						// Discarded changes shouldn't come back with the session
+						for file in &mut self.files {
+							file.is_modified = false;
+						}
+
+						self.exit()
+					}
+				}
+			}
+			Message::SelectTab(pane, index) => {
+				self.focus_pane(pane);
+				self.current = index;
+
+				Command::none()
+			}
+			Message::CloseTab(pane, index) => {
+				let shown_elsewhere = self.panes
+					.iter()
+					.any(|(other, state)| *other != pane && state.tabs.contains(&index));
+
+				// The file stays open as long as another pane has a tab for it
+				if !shown_elsewhere {
+					break 'handled self.update(Message::CloseIndex(index));
+				}
+
+				if let Some(state) = self.panes.get_mut(pane) {
+					state.remove_tab(index);
+
+					if state.tabs.is_empty() {
+						break 'handled self.update(Message::ClosePane(pane));
+					}
+
+					if pane == self.focus {
+						self.current = state.active;
+					}
 				}
 
 				Command::none()
 			}
-			Message::SelectFile(index) => {
+			Message::StartTabDrag(pane, index) => {
+				self.dragged_tab = Some((pane, index));
+				self.focus_pane(pane);
 				self.current = index;
 
 				Command::none()
 			}
-			Message::OpenURL(url) => {
-				if opener::open(url).is_err() {
-					eprintln!("Failed to open url {url}");
+			Message::TabHovered(pane, target) => {
+				let Some((dragged_pane, index)) = self.dragged_tab else {
+					break 'handled Command::none();
+				};
+
+				// Tabs can't be dragged between the pinned and the other tabs
+				if dragged_pane == pane && self.files[index].pinned == self.files[target].pinned {
+					if let Some(state) = self.panes.get_mut(pane) {
+						state.move_tab(index, target);
+					}
 				}
 
 				Command::none()
 			}
-			Message::ShowInExplorer(path) => {
-				if opener::open(path.clone()).is_err() {
-					eprintln!("Failed to open path {}", path.display());
+			Message::EndTabDrag => {
+				self.dragged_tab = None;
+
+				Command::none()
+			}
+			Message::TogglePin(index) => {
+				self.files[index].pinned = !self.files[index].pinned;
+
+				for (_, state) in self.panes.iter_mut() {
+					state.keep_pinned_first(&self.files);
 				}
 
 				Command::none()
 			}
-			Message::ShowModal(modal_type) => {
-				self.modal_shown = true;
-				self.modal_type = modal_type;
+			Message::ScrollTabs(pane, tabs) => self.panes
+				.get_mut(pane)
+				.map_or_else(Command::none, |state| {
+					let offset = state.tab_scroll + tabs * state.tab_step();
+
+					state.scroll_tabs(offset)
+				}),
+			Message::TabsScrolled(pane, viewport) => {
+				if let Some(state) = self.panes.get_mut(pane) {
+					state.tab_scroll = viewport.relative_offset().x;
+				}
 
 				Command::none()
 			}
-			Message::HideModal => {
-				self.modal_shown = false;
+			Message::EditIn(pane, action) => {
+				self.focus_pane(pane);
+
+				self.update(Message::Edit(action))
+			}
+			Message::FocusPane(pane) => {
+				self.focus_pane(pane);
 
 				Command::none()
 			}
-			Message::SelectTheme(theme) => {
-				self.theme = theme;
+			Message::SplitPane(axis) => {
+				let pane = Pane::new(vec![self.current], self.current);
 
-				config::save(self);
+				if let Some((pane, _)) = self.panes.split(axis, self.focus, pane) {
+					self.focus_pane(pane);
+				}
 
 				Command::none()
 			}
-			Message::SelectSyntaxTheme(theme) => {
-				self.highlighter_theme = theme;
+			Message::ClosePane(pane) => {
+				// The tabs of the pane move to the pane that takes its place
+				if let Some((state, sibling)) = self.panes.close(pane) {
+					if let Some(target) = self.panes.get_mut(sibling) {
+						for tab in state.tabs {
+							if !target.tabs.contains(&tab) {
+								target.tabs.push(tab);
+							}
+						}
 
-				config::save(self);
+						target.keep_pinned_first(&self.files);
+					}
+
+					if pane == self.focus {
+						self.focus_pane(sibling);
+					}
+				}
 
 				Command::none()
 			}
-			Message::None => Command::none(),
-		}
-	}
+			Message::PaneResized(pane_grid::ResizeEvent { split, ratio }) => {
+				self.panes.resize(split, ratio);
 
-	#[allow(clippy::too_many_lines)]
-	fn view(&self) -> Element<'_, Self::Message> {
-		use editor::components;
-		
-		let card = if self.modal_shown {
-			Some(match self.modal_type {
-				ModalType::About => components::about_modal(&self.theme),
-				ModalType::Settings => components::settings_modal(self),
-			})
-		} else {
-			None
-		};
+				Command::none()
+			}
+			Message::SelectNextFile | Message::SelectPreviousFile => {
+				let Some(state) = self.panes.get(self.focus) else {
+					break 'handled Command::none();
+				};
 
-		//let menu_tpl_1 = |items| Menu::new(items)
-		//	.max_width(180.0)
-		//	.offset(15.0)
-		//	.spacing(5.0);
-		let menu_tpl_2 = |items| Menu::new(items)
-			.max_width(180.0)
-			.offset(0.0)
-			.spacing(5.0);
+				let count = state.tabs.len();
+				let position = state.tabs.iter().position(|&tab| tab == self.current).unwrap_or(0);
 
-		let menu_bar = menu_bar![(
-            components::menubar_button(text("File"), None, Message::None),
-            {
-                let sub_menu = menu_tpl_2(menu_items![(components::menu_button(
-                    row![editor::icons::new_icon(12), components::icon_text("New"),]
-                        .align_items(Alignment::Center),
-                    Message::New
-                ))(
-                    components::menu_button(
-                        row![editor::icons::open_icon(12), components::icon_text("Open a file"),]
-                            .align_items(Alignment::Center),
-                        Message::Open
-                    )
-                )(
+				let next = if matches!(message, Message::SelectNextFile) {
+					(position + 1) % count
+				} else {
+					(position + count - 1) % count
+				};
+
+				self.current = state.tabs[next];
+
+				Command::none()
+			}
+			Message::NextRecentTab | Message::PreviousRecentTab => {
+				if !self.modal_shown || !matches!(self.modal_type, ModalType::TabSwitcher) {
+					self.modal_shown = true;
+					self.modal_type = ModalType::TabSwitcher;
+					self.tab_switcher = TabSwitcher::new(&self.files);
+				}
+
+				self.tab_switcher.cycle(matches!(message, Message::NextRecentTab));
+
+				// Nothing is held when run from the command palette, so there is no release to wait for
+				match self.tab_switcher.selected_tab() {
+					Some(index) if !tab_switcher::is_held(self.modifiers) => self.update(Message::SwitchToTab(index)),
+					_ => Command::none(),
+				}
+			}
+			Message::SwitchToTab(index) => {
+				self.modal_shown = false;
+
+				if index < self.files.len() {
+					self.current = index;
+				}
+
+				Command::none()
+			}
+			Message::ModifiersChanged(modifiers) => {
+				self.modifiers = modifiers;
+
+				let switching = self.modal_shown && matches!(self.modal_type, ModalType::TabSwitcher);
+
+				match self.tab_switcher.selected_tab() {
+					Some(index) if switching && !tab_switcher::is_held(modifiers) => {
+						self.update(Message::SwitchToTab(index))
+					}
+					_ => Command::none(),
+				}
+			}
+			Message::KeyPressed(key, modifiers, status) => {
+				if Chord::is_modifier(&key) {
+					break 'handled Command::none();
+				}
+
+				let list = match self.modal_type {
+					_ if !self.modal_shown => None,
+					ModalType::CommandPalette => Some(palette::filter(
+						palette::entries(self),
+						&self.palette.query,
+						&self.palette.recent,
+					).len()),
+					ModalType::QuickOpen => Some(self.quick_open.matches.len()),
+					ModalType::TabSwitcher => Some(self.tab_switcher.tabs.len()),
+					_ => None,
+				};
+
+				if let Some(count) = list {
+					let selected = match self.modal_type {
+						ModalType::QuickOpen => &mut self.quick_open.selected,
+						ModalType::TabSwitcher => &mut self.tab_switcher.selected,
+						_ => &mut self.palette.selected,
+					};
+
+					match &key {
+						keyboard::Key::Named(keyboard::key::Named::ArrowDown) if count > 0 => {
+							*selected = (*selected + 1) % count;
+
+							break 'handled Command::none();
+						}
+						keyboard::Key::Named(keyboard::key::Named::ArrowUp) if count > 0 => {
+							*selected = (*selected + count - 1) % count;
+
+							break 'handled Command::none();
+						}
+						keyboard::Key::Named(keyboard::key::Named::Escape) => {
+							self.modal_shown = false;
+
+							break 'handled Command::none();
+						}
+						_ => {}
+					}
+				}
+
+				let chord = Chord::new(modifiers, key);
+
+				if let Some(recording) = &mut self.recording {
+					if recording.chords.len() == keybindings::MAX_SEQUENCE_LENGTH {
+						recording.chords.clear();
+					}
+
+					recording.chords.push(chord);
+
+					break 'handled Command::none();
+				}
+
+				// Text inputs take every key, so bindings go first, except for
+				// the keys that type or move the cursor, which end any sequence
+				if status == event::Status::Captured && chord.is_typing() {
+					self.pending_chords.clear();
+
+					break 'handled Command::none();
+				}
+
+				self.pending_chords.push(chord);
+
+				match keybindings::resolve(&self.keybindings, &self.pending_chords) {
+					Resolution::Action(action) => {
+						self.pending_chords.clear();
+
+						self.update(action.message())
+					}
+					Resolution::Pending => Command::none(),
+					Resolution::Unbound => {
+						self.pending_chords.clear();
+
+						Command::none()
+					}
+				}
+			}
+			Message::Dismiss => {
+				if self.modal_shown {
+					self.modal_shown = false;
+					self.recording = None;
+				} else if let Some(workspace) = self.workspace.as_mut().filter(|workspace| workspace.prompt.is_some()) {
+					workspace.prompt = None;
+				} else if self.find_in_files.visible {
+					self.find_in_files.visible = false;
+				} else {
+					self.search.visible = false;
+				}
+
+				Command::none()
+			}
+			Message::SetLineEnding(line_ending) => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				if file.format.line_ending != line_ending {
+					file.format.line_ending = line_ending;
+					file.is_modified = true;
+					file.history.mark_dirty();
+				}
+
+				Command::none()
+			}
+			Message::ReopenWithEncoding(encoding) => {
+				assert!(self.current < self.files.len());
+
+				let file = &self.files[self.current];
+
+				// Reopening replaces the text, so unsaved changes are dealt with first
+				if file.is_modified && file.path.is_some() {
+					self.modal_shown = true;
+					self.modal_type = ModalType::UnsavedChangesBeforeReopen(file.id, encoding);
+
+					Command::none()
+				} else {
+					self.update(Message::ReloadWithEncoding(file.id, encoding))
+				}
+			}
+			Message::SaveAndReopen(id, encoding) => {
+				self.modal_shown = false;
+
+				let Some(file) = self.file(id) else {
+					break 'handled Command::none();
+				};
+
+				Command::perform(
+					save_file(file.path.clone(), buffer_text(&file.content), file.format, file.modified_at, self.backup),
+					move |result| Message::SavedBeforeReopen(id, encoding, result),
+				)
+			}
+			Message::SavedBeforeReopen(id, encoding, Ok(path)) => {
+				if let Some(index) = self.file_index(id) {
+					self.files[index].saved(path);
+				}
+
+				self.update(Message::ReloadWithEncoding(id, encoding))
+			}
+			Message::ReloadWithEncoding(id, encoding) => {
+				self.modal_shown = false;
+
+				if let Some(path) = self.file(id).and_then(|file| file.path.clone()) {
+					Command::perform(
+						async move { load_file_with_encoding(&path, Some(encoding)).await },
+						Message::FileReloaded,
+					)
+				} else {
+					Command::none()
+				}
+			}
+			Message::SaveWithEncoding(encoding) => {
+				assert!(self.current < self.files.len());
+
+				self.modal_shown = false;
+
+				let file = &self.files[self.current];
+				let id = file.id;
+
+				// The tab keeps its encoding if the text can't be saved in the new one
+				let mut format = file.format;
+
+				if format.encoding != encoding {
+					format.encoding = encoding;
+					format.bom = matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be);
+				}
+
+				Command::perform(
+					save_file(file.path.clone(), buffer_text(&file.content), format, file.modified_at, self.backup),
+					move |result| Message::SavedWithEncoding(id, format, result),
+				)
+			}
+			Message::SavedWithEncoding(id, format, Ok(path)) => {
+				self.remember_file(&path);
+
+				if let Some(index) = self.file_index(id) {
+					self.files[index].format = format;
+					self.files[index].saved(path);
+				}
+
+				Command::none()
+			}
+			Message::SetBom(bom) => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				if file.format.bom != bom {
+					file.format.bom = bom;
+					file.is_modified = true;
+					file.history.mark_dirty();
+				}
+
+				Command::none()
+			}
+			Message::ShowSearch => {
+				self.search.visible = true;
+
+				Command::batch([
+					text_input::focus(search::QUERY_ID.clone()),
+					text_input::select_all(search::QUERY_ID.clone()),
+				])
+			}
+			Message::HideSearch => {
+				self.search.visible = false;
+
+				Command::none()
+			}
+			Message::SearchQueryChanged(query) => {
+				self.search.query = query;
+				self.search.searched = None;
+
+				Command::none()
+			}
+			Message::ReplacementChanged(replacement) => {
+				self.search.replacement = replacement;
+
+				Command::none()
+			}
+			Message::SetSearchOptions(options) => {
+				self.search.options = options;
+				self.search.searched = None;
+
+				Command::none()
+			}
+			Message::FindNext | Message::FindPrevious => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				self.search.refresh(file);
+
+				let cursor = self.search.lines.offset(file.content.cursor_position());
+				let matches = &self.search.matches;
+
+				let found = if matches!(message, Message::FindNext) {
+					matches
+						.iter()
+						.find(|found| found.start > cursor)
+						.or(matches.first())
+				} else {
+					matches
+						.iter()
+						.rev()
+						.find(|found| found.start < cursor)
+						.or(matches.last())
+				};
+
+				if let Some(found) = found {
+					set_cursor(&mut file.content, self.search.lines.position(found.start));
+					file.history.end_group();
+				}
+
+				Command::none()
+			}
+			Message::ReplaceOne => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				if file.read_only {
+					break 'handled Command::none();
+				}
+
+				self.search.refresh(file);
+
+				let cursor = self.search.lines.offset(file.content.cursor_position());
+
+				// The first press only moves to a match, like in most editors
+				let Some(found) = self.search.matches.iter().find(|found| found.start == cursor).cloned() else {
+					break 'handled self.update(Message::FindNext);
+				};
+
+				let Some(Ok(regex)) = self.search.regex() else {
+					break 'handled Command::none();
+				};
+
+				let text = buffer_text(&file.content);
+
+				let Some(captures) = regex.captures_at(&text, found.start) else {
+					break 'handled Command::none();
+				};
+
+				let replacement = self.search.expand(&captures);
+
+				file.history.record_replacement(&file.content, self.history_limit);
+
+				// Selecting the match and pasting over it keeps the scroll position
+				if move_cursor_to(&mut file.content, self.search.lines.position(found.end), true) {
+					file.content.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(replacement))));
+				} else {
+					let replaced = format!("{}{replacement}{}", &text[..found.start], &text[found.end..]);
+					let cursor = LineIndex::new(&replaced).position(found.start + replacement.len());
+
+					file.content = content_with_cursor(&replaced, cursor);
+				}
+
+				file.revision += 1;
+				file.is_modified = !file.history.is_clean();
+				file.edited_at = Some(Instant::now());
+
+				// Moving on waits for the edited line to be laid out again
+				Command::perform(async {}, |()| Message::FindNext)
+			}
+			Message::ReplaceAll => {
+				assert!(self.current < self.files.len());
+
+				let file = &mut self.files[self.current];
+
+				if file.read_only {
+					break 'handled Command::none();
+				}
+				let text = buffer_text(&file.content);
+
+				if let Ok((replaced, count)) = self.search.replace_all(&text) {
+					if count > 0 {
+						file.history.record_replacement(&file.content, self.history_limit);
+						file.content = content_with_cursor(&replaced, file.content.cursor_position());
+						file.revision += 1;
+						file.is_modified = !file.history.is_clean();
+						file.edited_at = Some(Instant::now());
+					}
+				}
+
+				Command::none()
+			}
+			Message::ShowFindInFiles => {
+				self.find_in_files.visible = true;
+
+				if self.find_in_files.directory.is_none() {
+					self.find_in_files.directory = self.workspace
+						.as_ref()
+						.map(|workspace| workspace.root.clone())
+						.or_else(|| {
+							self.files[self.current]
+								.path
+								.as_ref()
+								.and_then(|path| path.parent())
+								.map(Path::to_path_buf)
+						});
+				}
+
+				Command::none()
+			}
+			Message::HideFindInFiles => {
+				self.find_in_files.visible = false;
+
+				Command::none()
+			}
+			Message::FindInFilesQueryChanged(query) => {
+				self.find_in_files.search.query = query;
+
+				Command::none()
+			}
+			Message::FindInFilesReplacementChanged(replacement) => {
+				self.find_in_files.search.replacement = replacement;
+
+				Command::none()
+			}
+			Message::SetFindInFilesOptions(options) => {
+				self.find_in_files.search.options = options;
+
+				Command::none()
+			}
+			Message::SetFindInFilesScope(scope) => {
+				self.find_in_files.scope = scope;
+
+				Command::none()
+			}
+			Message::PickSearchDirectory => Command::perform(pick_folder(), Message::SearchDirectoryPicked),
+			Message::SearchDirectoryPicked(directory) => {
+				if directory.is_some() {
+					self.find_in_files.directory = directory;
+					self.find_in_files.scope = find_in_files::Scope::Directory;
+				}
+
+				Command::none()
+			}
+			Message::IncludeChanged(include) => {
+				self.find_in_files.include = include;
+
+				Command::none()
+			}
+			Message::ExcludeChanged(exclude) => {
+				self.find_in_files.exclude = exclude;
+
+				Command::none()
+			}
+			Message::RunFindInFiles => {
+				let state = &mut self.find_in_files;
+
+				state.results.clear();
+				state.status = None;
+
+				match state.scope {
+					find_in_files::Scope::OpenTabs => {
+						for file in &self.files {
+							let matches = match find_in_files::search_text(&state.search, &buffer_text(&file.content)) {
+								Ok(matches) => matches,
+								Err(error) => {
+									state.status = Some(error.to_string());
+									break;
+								}
+							};
+
+							if !matches.is_empty() {
+								state.results.push(find_in_files::FileResults {
+									location: file.path.clone().map_or(
+										find_in_files::Location::Tab(file.id),
+										find_in_files::Location::Path,
+									),
+									name: file.name().to_string(),
+									matches,
+								});
+							}
+						}
+
+						Command::none()
+					}
+					find_in_files::Scope::Directory => {
+						let Some(directory) = state.directory.clone() else {
+							state.status = Some("Choose a folder to search in".to_string());
+
+							break 'handled Command::none();
+						};
+
+						state.is_searching = true;
+
+						Command::perform(
+							find_in_files::search_directory(
+								state.search.clone(),
+								directory,
+								state.include.clone(),
+								state.exclude.clone(),
+							),
+							Message::FindInFilesFinished,
+						)
+					}
+				}
+			}
+			Message::FindInFilesFinished(result) => {
+				let state = &mut self.find_in_files;
+
+				state.is_searching = false;
+
+				match result {
+					Ok(results) => state.results = results,
+					Err(Error::SearchFailed(error)) => state.status = Some(error),
+					Err(error) => self.error = Some(error),
+				}
+
+				Command::none()
+			}
+			Message::OpenSearchResult(location, position) => match location {
+				find_in_files::Location::Tab(id) => {
+					if let Some(index) = self.file_index(id) {
+						self.current = index;
+						self.move_cursor(position);
+					}
+
+					Command::none()
+				}
+				find_in_files::Location::Path(path) => self.update(Message::OpenAt(path, position)),
+			},
+			Message::ReplaceInFiles => {
+				let (_, on_disk) = self.replace_targets();
+
+				if on_disk.is_empty() {
+					self.update(Message::ApplyReplaceInFiles)
+				} else {
+					self.update(Message::ShowModal(ModalType::ConfirmReplaceInFiles(on_disk.len())))
+				}
+			}
+			Message::ApplyReplaceInFiles => {
+				self.modal_shown = false;
+
+				let search = self.find_in_files.search.clone();
+				let (tabs, on_disk) = self.replace_targets();
+				let mut replaced = 0;
+
+				// Open files are replaced in their buffers, so unsaved changes aren't lost
+				for index in tabs {
+					let file = &mut self.files[index];
+
+					if file.read_only {
+						continue;
+					}
+
+					if let Ok((text, count)) = search.replace_all(&buffer_text(&file.content)) {
+						if count > 0 {
+							file.history.record_replacement(&file.content, self.history_limit);
+							file.content = content_with_cursor(&text, file.content.cursor_position());
+							file.revision += 1;
+							file.is_modified = !file.history.is_clean();
+							file.edited_at = Some(Instant::now());
+
+							replaced += count;
+						}
+					}
+				}
+
+				Command::perform(
+					find_in_files::replace_in_files(search, on_disk, self.backup),
+					move |result| Message::ReplacedInFiles(result.map(|count| count + replaced)),
+				)
+			}
+			Message::ReplacedInFiles(result) => {
+				match result {
+					Ok(count) => {
+						self.find_in_files.status = Some(format!(
+							"Replaced {count} occurrence{}",
+							if count == 1 { "" } else { "s" }
+						));
+					}
+					Err(error) => self.error = Some(error),
+				}
+
+				self.find_in_files.results.clear();
+
+				Command::none()
+			}
+			Message::OpenAt(path, position) => {
+				if let Some(index) = self.files.iter().position(|file| file.path.as_ref() == Some(&path)) {
+					self.current = index;
+					self.move_cursor(position);
+
+					Command::none()
+				} else {
+					Command::perform(
+						async move { load_file(&path).await },
+						move |result| Message::FileOpenedAt(result, position),
+					)
+				}
+			}
+			Message::FileOpenedAt(result, position) => {
+				let opened = result.is_ok();
+				let command = self.update(Message::FileOpened(result));
+
+				if opened {
+					self.move_cursor(position);
+				}
+
+				command
+			}
+			Message::OpenURL(url) => {
+				if opener::open(url).is_err() {
+					eprintln!("Failed to open url {url}");
+				}
+
+				Command::none()
+			}
+			Message::ShowInExplorer(path) => {
+				if opener::open(path.clone()).is_err() {
+					eprintln!("Failed to open path {}", path.display());
+				}
+
+				Command::none()
+			}
+			Message::ShowModal(modal_type) => {
+				self.modal_shown = true;
+				self.modal_type = modal_type;
+
+				Command::none()
+			}
+			Message::HideModal => {
+				self.modal_shown = false;
+				self.recording = None;
+
+				Command::none()
+			}
+			Message::SelectTheme(theme) => {
+				self.theme = theme.clone();
+				self.configured_theme = theme;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::SelectSyntaxTheme(theme) => {
+				self.highlighter_theme = theme;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::SetHistoryLimit(limit) => {
+				self.history_limit = limit;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::SetBackup(backup) => {
+				self.backup = backup;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::OpenPath(path) => {
+				if let Some(index) = self.files.iter().position(|file| file.path.as_ref() == Some(&path)) {
+					self.current = index;
+
+					break 'handled Command::none();
+				}
+
+				Command::perform(async move { load_file(&path).await }, Message::FileOpened)
+			}
+			Message::ClearRecentFiles => {
+				self.recent_files.clear();
+				self.missing_files.clear();
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::OpenFolder => Command::perform(pick_folder(), Message::FolderPicked),
+			Message::FolderPicked(Some(root)) => self.open_workspace(root),
+			Message::OpenWorkspace(root) => {
+				self.modal_shown = false;
+
+				self.open_workspace(root)
+			}
+			Message::FileDropped(path) => {
+				if path.is_dir() {
+					self.dropped_folders.push_back(path);
+
+					Command::none()
+				} else {
+					self.start_drop();
+
+					open_dropped_file(path, false)
+				}
+			}
+			Message::DroppedFileOpened(_, Ok(file)) => {
+				self.pending_drops = self.pending_drops.saturating_sub(1);
+
+				self.update(Message::FileOpened(Ok(file)))
+			}
+			Message::DroppedFileOpened(path, Err(error)) | Message::FilesListed(path, Err(error)) => {
+				self.pending_drops = self.pending_drops.saturating_sub(1);
+
+				self.report_open_error(&path, &error);
+
+				Command::none()
+			}
+			Message::OpenFilesIn(directory) => {
+				self.modal_shown = false;
+
+				self.start_drop();
+
+				Command::perform(workspace::read_directory(directory.clone()), move |result| {
+					Message::FilesListed(directory, result)
+				})
+			}
+			Message::FilesListed(_, Ok(entries)) => {
+				let files: Vec<PathBuf> = entries
+					.into_iter()
+					.filter(|entry| !entry.is_directory)
+					.map(|entry| entry.path)
+					.collect();
+
+				// The listing itself was one of the pending drops
+				self.pending_drops = self.pending_drops.saturating_sub(1) + files.len();
+
+				Command::batch(files.into_iter().map(|path| open_dropped_file(path, true)))
+			}
+			Message::CloseFolder => {
+				self.workspace = None;
+				self.show_sidebar = false;
+
+				Command::none()
+			}
+			Message::ToggleSidebar => {
+				self.show_sidebar = !self.show_sidebar;
+
+				Command::none()
+			}
+			Message::RefreshWorkspace => {
+				let Some(workspace) = &mut self.workspace else {
+					break 'handled Command::none();
+				};
+
+				let directories: Vec<PathBuf> = workspace.children.keys().cloned().collect();
+
+				// Files created by other programs only show up in quick open once indexed again
+				let index = if workspace.is_indexing {
+					Command::none()
+				} else {
+					workspace.is_indexing = true;
+
+					index_workspace(workspace.root.clone())
+				};
+
+				Command::batch(directories.into_iter().map(read_directory).chain([index]))
+			}
+			Message::ToggleDirectory(path) => {
+				let Some(workspace) = &mut self.workspace else {
+					break 'handled Command::none();
+				};
+
+				workspace.select(path.clone(), true);
+
+				if workspace.expanded.remove(&path) {
+					break 'handled Command::none();
+				}
+
+				workspace.expanded.insert(path.clone());
+
+				if workspace.children.contains_key(&path) {
+					Command::none()
+				} else {
+					read_directory(path)
+				}
+			}
+			Message::DirectoryRead(directory, Ok(entries)) => {
+				if let Some(workspace) = &mut self.workspace {
+					workspace.children.insert(directory, entries);
+				}
+
+				Command::none()
+			}
+			Message::OpenEntry(path) => {
+				if let Some(workspace) = &mut self.workspace {
+					workspace.select(path.clone(), false);
+				}
+
+				self.update(Message::OpenPath(path))
+			}
+			Message::ShowPrompt(kind) => {
+				let Some(workspace) = &mut self.workspace else {
+					break 'handled Command::none();
+				};
+
+				workspace.prompt = Some(Prompt::new(kind));
+
+				Command::batch([
+					text_input::focus(workspace::PROMPT_ID.clone()),
+					text_input::select_all(workspace::PROMPT_ID.clone()),
+				])
+			}
+			Message::PromptChanged(name) => {
+				if let Some(prompt) = self.workspace.as_mut().and_then(|workspace| workspace.prompt.as_mut()) {
+					prompt.name = name;
+				}
+
+				Command::none()
+			}
+			Message::SubmitPrompt => {
+				let operation = self.workspace
+					.as_mut()
+					.and_then(|workspace| workspace.prompt.take())
+					.and_then(|prompt| prompt.operation());
+
+				match operation {
+					Some(operation) => self.update(Message::ApplyOperation(operation)),
+					None => Command::none(),
+				}
+			}
+			Message::ApplyOperation(operation) => {
+				self.modal_shown = false;
+
+				Command::perform(workspace::apply(operation), Message::OperationFinished)
+			}
+			Message::OperationFinished(Ok(operation)) => {
+				// Renamed or deleted files may be among the recent ones
+				self.missing_files = missing_files(&self.recent_files);
+
+				let Some(workspace) = &mut self.workspace else {
+					break 'handled Command::none();
+				};
+
+				let directories = operation.affected_directories();
+				let mut opened = Command::none();
+
+				match operation {
+					Operation::Create { path, is_directory } => {
+						if let Some(parent) = path.parent() {
+							workspace.expanded.insert(parent.to_path_buf());
+						}
+
+						workspace.select(path.clone(), is_directory);
+
+						if !is_directory {
+							workspace.files.push(path.clone());
+
+							opened = self.update(Message::OpenPath(path));
+						}
+					}
+					Operation::Rename { from, to } => {
+						workspace.rename(&from, &to);
+						workspace.select(to.clone(), to.is_dir());
+
+						// Open tabs follow their files to the new name. The watcher may
+						// have seen the old name disappear, which is no conflict.
+						for file in &mut self.files {
+							if let Some(path) = file.path.as_deref().and_then(|path| workspace::moved(path, &from, &to)) {
+								file.modified_at = watcher::modified_time(&path);
+								file.conflict = None;
+								file.path = Some(path);
+							}
+						}
+					}
+					Operation::Delete(path) => {
+						workspace.forget(&path);
+
+						let deleted: Vec<FileId> = self.files
+							.iter()
+							.filter(|file| file.path.as_ref().is_some_and(|file| file.starts_with(&path)))
+							.map(|file| file.id)
+							.collect();
+
+						if !deleted.is_empty() {
+							self.modal_shown = true;
+							self.modal_type = ModalType::DeletedFiles(deleted);
+						}
+					}
+				}
+
+				let Some(workspace) = &self.workspace else {
+					break 'handled opened;
+				};
+
+				let refresh = directories
+					.into_iter()
+					.filter(|directory| {
+						workspace.children.contains_key(directory) || workspace.expanded.contains(directory)
+					})
+					.map(read_directory);
+
+				Command::batch(refresh.chain([opened]))
+			}
+			Message::SetAutosave(mode) => {
+				self.autosave = mode;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::SetAutosaveDelay(delay) => {
+				self.autosave_delay = delay;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::Autosave => self.autosave(),
+			Message::AutoSaved(id, text, Ok(path)) => {
+				if let Some(file) = self.files
+					.iter_mut()
+					.find(|file| file.id == id && file.path.as_ref() == Some(&path))
+				{
+					// Edits made while saving keep the tab modified
+					if buffer_text(&file.content) == text {
+						file.saved(path);
+					} else {
+						file.modified_at = watcher::modified_time(&path);
+					}
+				}
+
+				Command::none()
+			}
+			Message::RecoverSwaps => {
+				self.modal_shown = false;
+
+				for swap in std::mem::take(&mut self.leftovers.swaps) {
+					self.recover(swap);
+				}
+
+				self.leftovers.remove();
+				self.leftovers = Leftovers::default();
+
+				Command::none()
+			}
+			Message::DiscardSwaps => {
+				self.modal_shown = false;
+
+				self.leftovers.remove();
+				self.leftovers = Leftovers::default();
+
+				Command::none()
+			}
+			Message::ShowSettingsPage(page) => {
+				self.settings_page = page;
+				self.recording = None;
+
+				Command::none()
+			}
+			Message::StartRecording(action) => {
+				self.recording = Some(Recording {
+					action,
+					chords: Vec::new(),
+				});
+
+				Command::none()
+			}
+			Message::ApplyRecording => {
+				if let Some(recording) = self.recording.take() {
+					if !recording.chords.is_empty() {
+						let sequence = Sequence(recording.chords);
+
+						// The keys are taken away from any other action, as a sequence
+						// can only be saved with one
+						self.keybindings.retain(|binding| binding.action != recording.action && binding.sequence != sequence);
+						self.keybindings.push(Keybinding {
+							sequence,
+							action: recording.action,
+						});
+
+						config::save(self);
+					}
+				}
+
+				Command::none()
+			}
+			Message::Unbind(action) => {
+				self.keybindings.retain(|binding| binding.action != action);
+				self.recording = None;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::CancelRecording => {
+				self.recording = None;
+
+				Command::none()
+			}
+			Message::ShowCommandPalette => {
+				self.modal_shown = true;
+				self.modal_type = ModalType::CommandPalette;
+				self.palette.query.clear();
+				self.palette.selected = 0;
+
+				text_input::focus(palette::INPUT_ID.clone())
+			}
+			Message::PaletteQueryChanged(query) => {
+				self.palette.query = query;
+				self.palette.selected = 0;
+
+				Command::none()
+			}
+			Message::RunCommand(index) => {
+				let entry = palette::filter(
+					palette::entries(self),
+					&self.palette.query,
+					&self.palette.recent,
+				)
+					.into_iter()
+					.nth(index);
+
+				self.modal_shown = false;
+
+				if let Some(entry) = entry {
+					self.palette.remember(&entry.label);
+
+					config::save(self);
+
+					self.update(entry.message)
+				} else {
+					Command::none()
+				}
+			}
+			Message::ShowQuickOpen => {
+				self.modal_shown = true;
+				self.modal_type = ModalType::QuickOpen;
+				self.quick_open = QuickOpen::default();
+				self.quick_open.matches = quick_open::matches(self);
+
+				text_input::focus(quick_open::INPUT_ID.clone())
+			}
+			Message::QuickOpenQueryChanged(query) => {
+				self.quick_open.query = query;
+				self.quick_open.selected = 0;
+				self.quick_open.matches = quick_open::matches(self);
+
+				Command::none()
+			}
+			Message::OpenQuickOpenEntry(path) => {
+				self.modal_shown = false;
+
+				self.update(Message::OpenPath(path))
+			}
+			Message::WorkspaceIndexed(root, files) => {
+				if let Some(workspace) = self.workspace.as_mut().filter(|workspace| workspace.root == root) {
+					workspace.files = files;
+					workspace.is_indexing = false;
+
+					if self.modal_shown && matches!(self.modal_type, ModalType::QuickOpen) {
+						let matches = quick_open::matches(self);
+
+						self.quick_open.selected = self.quick_open.selected.min(matches.len().saturating_sub(1));
+						self.quick_open.matches = matches;
+					}
+				}
+
+				Command::none()
+			}
+			Message::ResetKeybindings => {
+				self.keybindings = keybindings::defaults();
+				self.recording = None;
+
+				config::save(self);
+
+				Command::none()
+			}
+			Message::SaveSession => self.save_session(),
+			Message::FocusWindow => window::gain_focus(window::Id::MAIN),
+			Message::WindowFocused => {
+				// Files may have been moved or deleted by other programs meanwhile
+				self.missing_files = missing_files(&self.recent_files);
+
+				Command::none()
+			}
+			Message::FilesChanged(paths) => Command::batch(paths.into_iter().map(|path| {
+				Command::perform(watcher::read(path.clone()), move |state| {
+					Message::DiskStateRead(path, state)
+				})
+			})),
+			Message::DiskStateRead(path, Some(state)) => {
+				for index in 0..self.files.len() {
+					if self.files[index].path.as_ref() != Some(&path) {
+						continue;
+					}
+
+					let file = &mut self.files[index];
+
+					match &state {
+						DiskState::Deleted => file.conflict = Some(Conflict::Deleted),
+						DiskState::Present { text, format, modified_at } => {
+							if *modified_at == file.modified_at {
+								continue;
+							}
+
+							if **text == buffer_text(&file.content) {
+								file.modified_at = *modified_at;
+								file.conflict = None;
+							} else {
+								file.conflict = Some(Conflict::Changed {
+									text: text.clone(),
+									format: *format,
+									modified_at: *modified_at,
+								});
+
+								// Tabs without unsaved changes simply follow the file
+								if !file.is_modified {
+									self.reload_from_disk(index);
+								}
+							}
+						}
+					}
+				}
+
+				Command::none()
+			}
+			Message::ReloadFromDisk(index) => {
+				self.modal_shown = false;
+				self.reload_from_disk(index);
+
+				Command::none()
+			}
+			Message::KeepMine(index) => {
+				self.modal_shown = false;
+
+				let file = &mut self.files[index];
+
+				// Saving is allowed to overwrite the version that was seen
+				if let Some(Conflict::Changed { modified_at, .. }) = &file.conflict {
+					file.modified_at = *modified_at;
+				}
+
+				file.conflict = None;
+				file.is_modified = true;
+				file.history.mark_dirty();
+
+				Command::none()
+			}
+			Message::Overwrite(id, request) => {
+				self.modal_shown = false;
+
+				let Some(file) = self.file(id) else {
+					break 'handled Command::none();
+				};
+
+				let save = save_file(file.path.clone(), buffer_text(&file.content), file.format, None, self.backup);
+
+				// A close that was waiting for the save goes on once it's written
+				match request {
+					Some(request) => Command::perform(save, move |result| Message::SavedBeforeClosing(request, id, result)),
+					None => Command::perform(save, move |result| Message::FileSaved(id, result)),
+				}
+			}
+		} };
+
+		self.after_message(command, clicked, focus, current)
+	}
+
+	#[allow(clippy::too_many_lines)]
+	fn view(&self) -> Element<'_, Self::Message> {
+		use editor::components;
+		
+		let card = if self.modal_shown {
+			Some(match self.modal_type {
+				ModalType::About => components::about_modal(&self.theme),
+				ModalType::Settings => components::settings_modal(self),
+				ModalType::Encoding => components::encoding_modal(self),
+				ModalType::UnsavedChanges(id) => {
+					components::unsaved_changes_modal(self, id, Message::SaveAndClose(id), Message::DiscardAndClose(id))
+				}
+				ModalType::UnsavedChangesBeforeReopen(id, encoding) => components::unsaved_changes_modal(
+					self,
+					id,
+					Message::SaveAndReopen(id, encoding),
+					Message::ReloadWithEncoding(id, encoding),
+				),
+				ModalType::UnsavedChangesMany(ref request) => {
+					components::unsaved_changes_many_modal(self, request)
+				}
+				ModalType::CommandPalette => components::command_palette(self),
+				ModalType::QuickOpen => components::quick_open(self),
+				ModalType::TabSwitcher => components::tab_switcher(self),
+				ModalType::ChangedOnDisk(id, ref request) => {
+					components::changed_on_disk_modal(self, id, request.as_ref())
+				}
+				ModalType::Compare(index) => components::compare_modal(self, index),
+				ModalType::Recovery => components::recovery_modal(self),
+				ModalType::ConfirmDelete(ref path, is_directory) => {
+					components::confirm_delete_modal(self, path, is_directory)
+				}
+				ModalType::DroppedFolder(ref path) => components::dropped_folder_modal(self, path),
+				ModalType::ConfirmReplaceInFiles(count) => components::confirm_replace_in_files_modal(self, count),
+				ModalType::DeletedFiles(ref ids) => components::deleted_files_modal(self, ids),
+			})
+		} else {
+			None
+		};
+
+		//let menu_tpl_1 = |items| Menu::new(items)
+		//	.max_width(180.0)
+		//	.offset(15.0)
+		//	.spacing(5.0);
+		let menu_tpl_2 = |items| Menu::new(items)
+			.max_width(180.0)
+			.offset(0.0)
+			.spacing(5.0);
+
+		let recent_menu = {
+			let mut items: Vec<Item<'_, Message, Theme, iced::Renderer>> = self.recent_files
+				.iter()
+				.map(|path| {
+					let label = components::icon_text(&path.display());
+
+					Item::new(if self.missing_files.contains(path) {
+						components::menu_button_disabled(label)
+					} else {
+						components::menu_button(label, Message::OpenPath(path.clone()))
+					})
+				})
+				.collect();
+
+			if items.is_empty() {
+				items.push(Item::new(components::menu_button_disabled(
+					components::icon_text("No recent files"),
+				)));
+			}
+
+			items.push(Item::new(components::separator(&self.theme)));
+			items.push(Item::new(if self.recent_files.is_empty() {
+				components::menu_button_disabled(components::icon_text("Clear Recent"))
+			} else {
+				components::menu_button(components::icon_text("Clear Recent"), Message::ClearRecentFiles)
+			}));
+
+			menu_tpl_2(items).width(360.0)
+		};
+
+		let menu_bar = menu_bar![(
+            components::menubar_button(text("File"), None, Message::None),
+            {
+                let sub_menu = menu_tpl_2(menu_items![(components::menu_button(
+                    components::menu_item(editor::icons::new_icon(12), "New", self.shortcut(Action::New)),
+                    Message::New
+                ))(
                     components::menu_button(
-                        row![editor::icons::save_icon(12), components::icon_text("Save"),]
-                            .align_items(Alignment::Center),
+                        components::menu_item(editor::icons::open_icon(12), "Open a file", self.shortcut(Action::Open)),
+                        Message::Open
+                    )
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::folder_icon(12), "Open Folder", self.shortcut(Action::OpenFolder)),
+                        Message::OpenFolder
+                    )
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::search_icon(12), "Go to File", self.shortcut(Action::QuickOpen)),
+                        Message::ShowQuickOpen
+                    )
+                )(
+                    if self.workspace.is_some() {
+                        components::menu_button(
+                            components::menu_item(editor::icons::close_icon(12), "Close Folder", None),
+                            Message::CloseFolder
+                        )
+                    } else {
+                        components::menu_button_disabled(
+                            components::menu_item(editor::icons::close_icon(12), "Close Folder", None),
+                        )
+                    }
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::history_icon(12), "Open Recent", None),
+                        Message::None
+                    ),
+                    recent_menu
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::save_icon(12), "Save", self.shortcut(Action::Save)),
                         Message::Save
                     )
                 )(
                     components::menu_button(
-                        row![editor::icons::save_as_icon(12), components::icon_text("Save As"),]
-                            .align_items(Alignment::Center),
-                        Message::SaveAs
+                        components::menu_item(editor::icons::save_as_icon(12), "Save As", self.shortcut(Action::SaveAs)),
+                        Message::SaveAs
+                    )
+                )(
+                    components::menu_button(
+                        row![editor::icons::encoding_icon(12), components::icon_text("Encoding"),]
+                            .align_items(Alignment::Center),
+                        Message::ShowModal(ModalType::Encoding)
+                    )
+                )(
+                    if let Some(path) = self.files[self.current].path.clone() {
+                        components::menu_button(
+                            row![editor::icons::eye_icon(12), components::icon_text("Show in Explorer"),]
+                                .align_items(Alignment::Center),
+                            Message::ShowInExplorer(path),
+                        )
+                    } else {
+                        components::menu_button_disabled(
+                            row![editor::icons::eye_icon(12), components::icon_text("Show in Explorer"),]
+                                .align_items(Alignment::Center),
+                        )
+                    }
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::close_icon(12), "Close", self.shortcut(Action::Close)),
+                        Message::Close
+                    )
+                )(
+                    if self.closed_tabs.is_empty() {
+                        components::menu_button_disabled(
+                            components::menu_item(editor::icons::reopen_icon(12), "Reopen Closed Tab", self.shortcut(Action::ReopenClosedTab)),
+                        )
+                    } else {
+                        components::menu_button(
+                            components::menu_item(editor::icons::reopen_icon(12), "Reopen Closed Tab", self.shortcut(Action::ReopenClosedTab)),
+                            Message::ReopenClosedTab
+                        )
+                    }
+                )(
+                    components::separator(&self.theme)
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::settings_icon(12), "Settings", self.shortcut(Action::Settings)),
+                        Message::ShowModal(ModalType::Settings)
                     )
+                )])
+                .width(240.0);
+
+                sub_menu
+            }
+        )(
+            components::menubar_button(text("Edit"), None, Message::None),
+            {
+                let history = &self.files[self.current].history;
+
+                let sub_menu = menu_tpl_2(menu_items![(
+                    if history.can_undo() {
+                        components::menu_button(
+                            components::menu_item(editor::icons::undo_icon(12), "Undo", self.shortcut(Action::Undo)),
+                            Message::Undo
+                        )
+                    } else {
+                        components::menu_button_disabled(
+                            components::menu_item(editor::icons::undo_icon(12), "Undo", self.shortcut(Action::Undo)),
+                        )
+                    }
                 )(
-                    if let Some(path) = self.files[self.current].path.clone() {
+                    if history.can_redo() {
                         components::menu_button(
-                            row![editor::icons::eye_icon(12), components::icon_text("Show in Explorer"),]
-                                .align_items(Alignment::Center),
-                            Message::ShowInExplorer(path),
+                            components::menu_item(editor::icons::redo_icon(12), "Redo", self.shortcut(Action::Redo)),
+                            Message::Redo
                         )
                     } else {
                         components::menu_button_disabled(
-                            row![editor::icons::eye_icon(12), components::icon_text("Show in Explorer"),]
-                                .align_items(Alignment::Center),
+                            components::menu_item(editor::icons::redo_icon(12), "Redo", self.shortcut(Action::Redo)),
                         )
                     }
+                )(
+                    components::separator(&self.theme)
                 )(
                     components::menu_button(
-                        row![editor::icons::close_icon(12), components::icon_text("Close"),]
-                            .align_items(Alignment::Center),
-                        Message::Close
+                        components::menu_item(editor::icons::search_icon(12), "Find and Replace", self.shortcut(Action::Find)),
+                        Message::ShowSearch
                     )
                 )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::files_icon(12), "Find in Files", self.shortcut(Action::FindInFiles)),
+                        Message::ShowFindInFiles
+                    )
+                )])
+                .width(240.0);
+
+                sub_menu
+            }
+        )(
+            components::menubar_button(text("View"), None, Message::None),
+            {
+                let sub_menu = menu_tpl_2(menu_items![(components::menu_button(
+                    components::menu_item(editor::icons::sidebar_icon(12), "Toggle Sidebar", self.shortcut(Action::ToggleSidebar)),
+                    Message::ToggleSidebar
+                ))(
                     components::separator(&self.theme)
                 )(
                     components::menu_button(
-                        row![editor::icons::settings_icon(12), components::icon_text("Settings"),]
+                        components::menu_item(editor::icons::split_icon(12), "Split Right", self.shortcut(Action::SplitRight)),
+                        Message::SplitPane(pane_grid::Axis::Vertical)
+                    )
+                )(
+                    components::menu_button(
+                        components::menu_item(editor::icons::split_icon(12), "Split Down", self.shortcut(Action::SplitDown)),
+                        Message::SplitPane(pane_grid::Axis::Horizontal)
+                    )
+                )(
+                    if self.panes.len() > 1 {
+                        components::menu_button(
+                            components::menu_item(editor::icons::close_icon(12), "Close Pane", None),
+                            Message::ClosePane(self.focus)
+                        )
+                    } else {
+                        components::menu_button_disabled(
+                            components::menu_item(editor::icons::close_icon(12), "Close Pane", None),
+                        )
+                    }
+                )])
+                .width(240.0);
+
+                sub_menu
+            }
+        )(
+            components::menubar_button(text("Help"), None, Message::None),
+            {
+                let sub_menu = menu_tpl_2(menu_items![(components::menu_button(
+                    row![editor::icons::info_icon(12), text("   About"),]
+                        .align_items(Alignment::Center),
+                    Message::ShowModal(ModalType::About)
+                ))(
+                    components::menu_button(
+                        row![editor::icons::git_icon(12), text("   Source"),]
                             .align_items(Alignment::Center),
-                        Message::ShowModal(ModalType::Settings)
+                        Message::OpenURL("https://github.com/Theboiboi8/multi_tab_text_editor")
                     )
                 )])
                 .width(180.0);
 
-                sub_menu
-            }
-        )(
-            components::menubar_button(text("Help"), None, Message::None),
-            {
-                let sub_menu = menu_tpl_2(menu_items![(components::menu_button(
-                    row![editor::icons::info_icon(12), text("   About"),]
-                        .align_items(Alignment::Center),
-                    Message::ShowModal(ModalType::About)
-                ))(
-                    components::menu_button(
-                        row![editor::icons::git_icon(12), text("   Source"),]
-                            .align_items(Alignment::Center),
-                        Message::OpenURL("https://github.com/Theboiboi8/multi_tab_text_editor")
-                    )
-                )])
-                .width(180.0);
+                sub_menu
+            }
+        )]
+			.draw_path(menu::DrawPath::Backdrop);
+
+		let (matches, match_count) = if self.search.visible && self.search.error.is_none() {
+			let cursor = self.search.lines.offset(self.files[self.current].content.cursor_position());
+			let found = &self.search.matches;
+			let current = found.iter().position(|found| found.start == cursor);
+
+			let matches: Vec<_> = found
+				.iter()
+				.enumerate()
+				.flat_map(|(position, found)| {
+					self.search.lines
+						.line_ranges(found)
+						.into_iter()
+						.map(move |(line, columns)| editor::highlighter::LineMatch {
+							line,
+							columns,
+							current: current == Some(position),
+						})
+				})
+				.collect();
+
+			(Arc::new(matches), Some((current, found.len())))
+		} else {
+			(Arc::default(), None)
+		};
+
+		let find_bar = self.search.visible.then(|| components::find_bar(self, self.search.error.as_ref()));
+
+		let find_in_files_panel = self.find_in_files.visible.then(|| components::find_in_files_panel(self));
+
+		let panes = PaneGrid::new(&self.panes, |pane, state, _| {
+			let focused = pane == self.focus;
+			let active = if focused { self.current } else { state.active };
+			let file = &self.files[active];
+
+			let tabs = components::tab_strip(self, pane, state, active);
+
+			let content = match &state.view {
+				Some(view) if !focused => &view.content,
+				_ => &file.content,
+			};
+
+			let input = text_editor(content)
+				.on_action(move |action| Message::EditIn(pane, action))
+				.font(*JETBRAINS_MONO)
+				.height(Length::Fill)
+				.highlight::<editor::highlighter::Highlighter>(
+					editor::highlighter::Settings {
+						syntax: highlighter::Settings {
+							theme: self.highlighter_theme,
+							extension: file
+								.path
+								.as_ref()
+								.and_then(|path| path.extension()?.to_str())
+								.unwrap_or("rs")
+								.to_string(),
+						},
+						// Search matches are only shown in the focused pane
+						matches: if focused { matches.clone() } else { Arc::default() },
+					},
+					editor::highlighter::Highlight::to_format,
+				);
+
+			pane_grid::Content::new(Column::new().push(tabs).push(input).spacing(10))
+		})
+			.on_click(Message::FocusPane)
+			.on_resize(10, Message::PaneResized)
+			.spacing(10);
+
+		let status_bar = {
+			let status = if !self.pending_chords.is_empty() {
+				text(format!(
+					"{} was pressed. Waiting for the next key...",
+					Sequence(self.pending_chords.clone())
+				))
+			} else if let Some(Error::IOFailed(error)) = self.error.as_ref() {
+				text(error.to_string())
+			} else if let Some(Error::EncodingFailed(encoding)) = self.error.as_ref() {
+				text(format!("The text contains characters that can't be saved as {encoding}"))
+			} else if let Some(Error::SearchFailed(error)) = self.error.as_ref() {
+				text(error)
+			} else if let Some(Error::InvalidKeybinding(error)) = self.error.as_ref() {
+				text(format!("Invalid keybinding: {error}"))
+			} else if let Some(Error::InvalidArgument(error)) = self.error.as_ref() {
+				text(error)
+			} else if let Some(Error::ChangedOnDisk) = self.error.as_ref() {
+				text("The file was changed by another program")
+			} else if let Some(Error::OpenFailed(failures)) = self.error.as_ref() {
+				text(format!("Failed to open {}", failures.join(", ")))
+			} else {
+				match self.files[self.current]
+					.path
+					.as_deref()
+					.and_then(Path::to_str)
+				{
+					Some(path) => text(path).size(14),
+					None => text("New file").size(14),
+				}
+			};
+
+			let position = {
+				let (line, column) = self.files[self.current].content.cursor_position();
+
+				text(format!("{}:{}", line + 1, column + 1))
+			};
+
+			let format = self.files[self.current].format;
+
+			let read_only = text(if self.files[self.current].read_only { "Read-only" } else { "" });
+
+			let indentation = text(format.indentation.to_string());
+
+			let encoding = components::menubar_button(
+				text(format!("{}{}", format.encoding, if format.bom { " with BOM" } else { "" })),
+				Some("Reopen or save with a different encoding"),
+				Message::ShowModal(ModalType::Encoding),
+			);
+
+			let line_ending = pick_list(
+				LineEnding::ALL.as_slice(),
+				Some(format.line_ending),
+				Message::SetLineEnding,
+			)
+				.text_size(12)
+				.padding([0, 6]);
+
+			let match_count = text(match match_count {
+				None => String::new(),
+				Some((_, 0)) => "No matches".to_string(),
+				Some((Some(current), count)) => format!("{} of {count} matches", current + 1),
+				Some((None, 1)) => "1 match".to_string(),
+				Some((None, count)) => format!("{count} matches"),
+			});
+
+			row![status, horizontal_space(), match_count, read_only, indentation, encoding, line_ending, position]
+				.spacing(10)
+				.align_items(Alignment::Center)
+		};
+
+		Modal::new(
+			container(
+				Column::new()
+					.push(menu_bar)
+					.push(
+						Row::new()
+							.push_maybe(self.show_sidebar.then(|| components::sidebar(self)))
+							.push(
+								Column::new()
+									.push_maybe(
+										self.files[self.current]
+											.conflict
+											.as_ref()
+											.map(|conflict| components::conflict_banner(self.current, conflict))
+									)
+									.push_maybe(find_bar)
+									.push(panes)
+									.spacing(10)
+							)
+							.spacing(10)
+					)
+					.push_maybe(find_in_files_panel)
+					.push(status_bar)
+					.spacing(10),
+			)
+				.padding(10),
+			card,
+		)
+			.into()
+	}
+
+	fn theme(&self) -> Theme {
+		self.theme.clone()
+	}
+
+	fn subscription(&self) -> Subscription<Message> {
+		Subscription::batch([
+			event::listen_with(|event, status| match event {
+				Event::Window(_, window::Event::CloseRequested) => Some(Message::CloseRequested),
+				Event::Window(_, window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+				Event::Window(_, window::Event::Focused) => Some(Message::WindowFocused),
+				// Captured key presses are needed too, to finish multi-key sequences
+				Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+					Some(Message::KeyPressed(key, modifiers, status))
+				}
+				Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => Some(Message::ModifiersChanged(modifiers)),
+				_ => None,
+			}),
+			if self.dragged_tab.is_some() {
+				event::listen_with(|event, _| match event {
+					Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => Some(Message::EndTabDrag),
+					_ => None,
+				})
+			} else {
+				Subscription::none()
+			},
+			iced::time::every(session::SAVE_INTERVAL).map(|_| Message::SaveSession),
+			watcher::watch(self.files.iter().filter_map(|file| file.path.clone()).collect()),
+			#[cfg(unix)]
+			if self.single_instance {
+				instance::listen()
+			} else {
+				Subscription::none()
+			},
+			if self.autosave == autosave::Mode::Off {
+				Subscription::none()
+			} else {
+				iced::time::every(autosave::TICK).map(|_| Message::Autosave)
+			},
+		])
+	}
+}
+
+impl Editor {
+	/// Closes the tab at the given index, moving the focus to a neighbouring
+	/// tab if it was the active one.
+	fn close_file(&mut self, index: usize) {
+		assert!(index < self.files.len());
+
+		self.dragged_tab = None;
+
+		// An empty new tab has nothing worth reopening
+		let file = &self.files[index];
+
+		if file.path.is_some() || file.is_modified {
+			let position_in = |state: &Pane| state.tabs.iter().position(|&tab| tab == index);
+
+			// The tab is reopened in the focused pane if it was shown there
+			let place = self.panes
+				.get(self.focus)
+				.and_then(position_in)
+				.map(|position| (self.focus, position))
+				.or_else(|| self.panes.iter().find_map(|(pane, state)| Some((*pane, position_in(state)?))));
+
+			if let Some((pane, position)) = place {
+				if self.closed_tabs.len() == MAX_CLOSED_TABS {
+					self.closed_tabs.pop_front();
+				}
+
+				self.closed_tabs.push_back(ClosedTab {
+					pane,
+					position,
+					tab: session::Tab::capture(file),
+				});
+			}
+		}
+
+		if let Some(swap) = &self.files[index].swap {
+			autosave::remove_swap(swap);
+		}
+
+		// Results in a tab without a file can't be opened anymore
+		let location = find_in_files::Location::Tab(self.files[index].id);
+
+		self.find_in_files.results.retain(|results| results.location != location);
+
+		if self.files.len() == 1 {
+			self.files[0] = File::empty();
+			self.current = 0;
+
+			// Only the focused pane stays, showing the empty tab
+			let others: Vec<pane_grid::Pane> = self.panes
+				.iter()
+				.map(|(pane, _)| *pane)
+				.filter(|pane| *pane != self.focus)
+				.collect();
+
+			for pane in others {
+				self.panes.close(pane);
+			}
+
+			if let Some(state) = self.panes.get_mut(self.focus) {
+				*state = Pane::new(vec![0], 0);
+			}
+
+			return;
+		}
+
+		self.files.remove(index);
+
+		let mut emptied = Vec::new();
+
+		for (pane, state) in self.panes.iter_mut() {
+			state.file_closed(index);
+
+			if state.tabs.is_empty() {
+				emptied.push(*pane);
+			}
+		}
+
+		for pane in emptied {
+			if let Some((_, sibling)) = self.panes.close(pane) {
+				if pane == self.focus {
+					self.focus = sibling;
+				}
+			}
+		}
+
+		self.current = self.panes
+			.get(self.focus)
+			.map_or(0, |state| state.active)
+			.min(self.files.len() - 1);
+	}
+
+	/// Shows the file if it's open, returning whether it was.
+	fn focus_open_file(&mut self, path: Option<&Path>) -> bool {
+		let Some(index) = path.and_then(|path| self.files.iter().position(|file| file.path.as_deref() == Some(path))) else {
+			return false;
+		};
+
+		self.current = index;
+
+		true
+	}
+
+	/// Puts a closed tab back where it was, or in the focused pane if its own
+	/// pane was closed since.
+	fn reopen_tab(&mut self, closed: &ClosedTab, file: File) {
+		self.files.push(file);
+
+		let index = self.files.len() - 1;
+		let pane = if self.panes.get(closed.pane).is_some() {
+			closed.pane
+		} else {
+			self.focus
+		};
+
+		if let Some(state) = self.panes.get_mut(pane) {
+			let position = closed.position.min(state.tabs.len());
+
+			state.tabs.insert(position, index);
+			state.keep_pinned_first(&self.files);
+		}
+
+		self.focus_pane(pane);
+		self.current = index;
+	}
+
+	/// The name shown on the tab of a file, which includes parent folders
+	/// when other open files have the same name.
+	fn tab_name(&self, index: usize) -> String {
+		let file = &self.files[index];
+
+		match &file.path {
+			Some(path) => distinct_name(path, self.files.iter().filter_map(|file| file.path.as_deref())),
+			None => file.name().to_string(),
+		}
+	}
+
+	/// Moves the focus to another pane. The pane's copy of its file's content
+	/// becomes the file's content, and the previously focused pane keeps the
+	/// content as its copy if it shows the same file.
+	/// Brings everything in line with a message that was just handled, given
+	/// the focused pane and current tab from before it.
+	fn after_message(
+		&mut self,
+		mut command: Command<Message>,
+		clicked: bool,
+		focus: pane_grid::Pane,
+		current: usize,
+	) -> Command<Message> {
+		// Folders dropped while another dialog was shown wait for it to close
+		if !self.modal_shown {
+			if let Some(path) = self.dropped_folders.pop_front() {
+				command = Command::batch([command, self.update(Message::ShowModal(ModalType::DroppedFolder(path)))]);
+			}
+		}
+
+		// The matches are only found again once the query or the text changes
+		if self.search.visible {
+			self.search.refresh(&self.files[self.current]);
+		}
+
+		self.sync_panes();
+
+		// The current tab is always the most recently used one
+		self.files[self.current].activated_at = Some(Instant::now());
+
+		// Tabs that are clicked are already in view
+		if clicked || self.focus != focus || self.current == current {
+			return command;
+		}
+
+		let reveal = self.panes
+			.get_mut(self.focus)
+			.map_or_else(Command::none, Pane::reveal_active);
+
+		Command::batch([command, reveal])
+	}
+
+	fn focus_pane(&mut self, pane: pane_grid::Pane) {
+		if pane == self.focus {
+			return;
+		}
+
+		let previous = self.focus;
+
+		let Some(state) = self.panes.get_mut(pane) else {
+			return;
+		};
+
+		let active = state.active;
+
+		if let Some(view) = state.view.take() {
+			let file = &mut self.files[active];
+			let content = view.into_content(file);
+			let content = std::mem::replace(&mut file.content, content);
+			let revision = file.revision;
+
+			if let Some(state) = self.panes.get_mut(previous).filter(|state| state.active == active) {
+				state.view = Some(View { content, revision });
+			}
+		}
+
+		self.focus = pane;
+		self.current = active;
+	}
+
+	/// Makes the focused pane show the current file, gives other panes showing
+	/// it an up to date copy of its content, and hands the copy back to the
+	/// file once the focused pane shows another one.
+	fn sync_panes(&mut self) {
+		let current = self.current;
+
+		for (pane, state) in self.panes.iter_mut() {
+			if *pane == self.focus {
+				if !state.tabs.contains(&current) {
+					state.tabs.push(current);
+				}
+
+				state.active = current;
+				state.view = None;
+
+				continue;
+			}
+
+			let file = &mut self.files[state.active];
+
+			if state.active == current {
+				match &mut state.view {
+					Some(view) => view.refresh(file),
+					None => state.view = Some(View::new(file)),
+				}
+			} else if let Some(view) = state.view.take() {
+				file.content = view.into_content(file);
+			}
+		}
+	}
+
+	fn close_files(&mut self, indices: &[usize]) {
+		let mut indices = indices.to_vec();
+
+		indices.sort_unstable();
+		indices.dedup();
+
+		for index in indices.into_iter().rev() {
+			self.close_file(index);
+		}
+	}
+
+	/// Replaces the text of the tab with the version on disk it conflicts with.
+	/// The reload can be undone.
+	fn reload_from_disk(&mut self, index: usize) {
+		let file = &mut self.files[index];
+
+		let Some(Conflict::Changed { text, format, modified_at }) = file.conflict.take() else {
+			return;
+		};
+
+		file.history.record_replacement(&file.content, self.history_limit);
+		file.history.mark_clean();
+		file.content = content_with_cursor(&text, file.content.cursor_position());
+		file.revision += 1;
+		file.format = format;
+		file.modified_at = modified_at;
+		file.is_modified = false;
+	}
+
+	/// Writes the session to disk in the background if it changed since it
+	/// was last written.
+	fn save_session(&mut self) -> Command<Message> {
+		if !self.persist_session || !self.session_changed {
+			return Command::none();
+		}
+
+		self.session_changed = false;
+
+		let session = Session::capture(self);
+
+		if self.session.as_ref() == Some(&session) {
+			return Command::none();
+		}
+
+		self.session = Some(session.clone());
+
+		Command::perform(session::save(session), |()| Message::None)
+	}
+
+	/// Saves the session and closes the window, which no longer needs its
+	/// recovery files.
+	fn exit(&mut self) -> Command<Message> {
+		// Written right away, as the window won't wait for a background write
+		if self.persist_session {
+			session::save_now(&Session::capture(self));
+		}
+
+		self.recovery.remove();
 
-                sub_menu
-            }
-        )]
-			.draw_path(menu::DrawPath::Backdrop);
+		window::close(window::Id::MAIN)
+	}
 
-		let mut tabs = Vec::new();
+	/// Saves or snapshots the modified files that weren't edited for the
+	/// autosave delay.
+	fn autosave(&mut self) -> Command<Message> {
+		let delay = Duration::from_secs(self.autosave_delay);
+		let mut commands = Vec::new();
 
-		for (index, file) in self.files.iter().enumerate() {
-			tabs.push(components::tab(
-				text(format!(
-					"{}{}",
-					match &file.path {
-						None => {
-							"New file"
-						}
-						Some(path) => {
-							match path.file_name() {
-								None => "Error",
-								Some(file_name) => file_name.to_str().unwrap_or("Error"),
-							}
-						}
-					},
-					if file.is_modified { "*" } else { "" }
-				))
-					.width(Length::Fill)
-					.into(),
-				Message::SelectFile(index),
-				index,
-				self.current == index,
-			));
-		}
-
-		let tabs = Row::from_vec(tabs).width(Length::Fill).spacing(5);
-
-		let input = text_editor(&self.files[self.current].content)
-			.on_action(Message::Edit)
-			.font(*JETBRAINS_MONO)
-			.height(Length::Fill)
-			.highlight::<Highlighter>(
-				highlighter::Settings {
-					theme: self.highlighter_theme,
-					extension: self.files[self.current]
-						.path
-						.as_ref()
-						.and_then(|path| path.extension()?.to_str())
-						.unwrap_or("rs")
-						.to_string(),
-				},
-				|highlight, _theme| highlight.to_format(),
-			);
+		for file in &mut self.files {
+			if !file.is_modified {
+				if let Some(swap) = file.swap.take() {
+					autosave::remove_swap(&swap);
+				}
 
-		let status_bar = {
-			let status = if let Some(Error::IOFailed(error)) = self.error.as_ref() {
-				text(error.to_string())
-			} else {
-				match self.files[self.current]
-					.path
-					.as_deref()
-					.and_then(Path::to_str)
-				{
-					Some(path) => text(path).size(14),
-					None => text("New file").size(14),
+				continue;
+			}
+
+			if file.edited_at.is_none_or(|edited_at| edited_at.elapsed() < delay) {
+				continue;
+			}
+
+			match self.autosave {
+				autosave::Mode::Off => {}
+				autosave::Mode::SaveFiles => {
+					if file.path.is_none() || file.read_only || file.conflict.is_some() {
+						continue;
+					}
+
+					let id = file.id;
+					let text = buffer_text(&file.content);
+
+					commands.push(Command::perform(
+						save_file(file.path.clone(), text.clone(), file.format, file.modified_at, self.backup),
+						move |result| Message::AutoSaved(id, text, result),
+					));
 				}
-			};
+				autosave::Mode::SwapFiles => {
+					file.swap = self.recovery.write(file.swap.as_deref(), &Swap {
+						path: file.path.clone(),
+						text: buffer_text(&file.content),
+						format: file.format,
+						cursor: file.content.cursor_position(),
+					});
+				}
+			}
 
-			let position = {
-				let (line, column) = self.files[self.current].content.cursor_position();
+			file.edited_at = None;
+		}
 
-				text(format!("{}:{}", line + 1, column + 1))
-			};
+		Command::batch(commands)
+	}
+
+	/// Opens a recovered swap file as a modified tab, or puts it into the tab
+	/// that already has its file open.
+	fn recover(&mut self, swap: Swap) {
+		// An untitled tab the session brought back with the same text is the
+		// one the swap file was written for
+		let existing = self.files.iter().position(|file| match &swap.path {
+			Some(path) => file.path.as_ref() == Some(path),
+			None => file.path.is_none() && buffer_text(&file.content) == swap.text,
+		});
+
+		if let Some(index) = existing {
+			let file = &mut self.files[index];
+
+			if swap.text != buffer_text(&file.content) {
+				file.history.record_replacement(&file.content, self.history_limit);
+				file.history.mark_dirty();
+				file.content = content_with_cursor(&swap.text, swap.cursor);
+				file.revision += 1;
+				file.format = swap.format;
+				file.is_modified = true;
+				file.edited_at = Some(Instant::now());
+			}
+
+			self.current = index;
+
+			return;
+		}
 
-			row![status, horizontal_space(), position]
+		self.files.push(File {
+			modified_at: swap.path.as_deref().and_then(watcher::modified_time),
+			path: swap.path,
+			content: content_with_cursor(&swap.text, swap.cursor),
+			is_modified: true,
+			format: swap.format,
+			history: History::unsaved(),
+			edited_at: Some(Instant::now()),
+			..File::empty()
+		});
+
+		self.current = self.files.len() - 1;
+	}
+
+	/// Adds a file that couldn't be opened to the error in the status bar, so
+	/// that one failure among many dropped files doesn't hide the others.
+	fn report_open_error(&mut self, path: &Path, error: &Error) {
+		let reason = match error {
+			Error::IOFailed(kind) => kind.to_string(),
+			Error::NotText => "not a text file".to_string(),
+			error => format!("{error:?}"),
 		};
 
-		Modal::new(
-			container(
-				Column::new()
-					.push(menu_bar)
-					.push(tabs)
-					.push(input)
-					.push(status_bar)
-					.spacing(10),
-			)
-				.padding(10),
-			card,
-		)
-			.into()
+		let failure = format!(
+			"{} ({reason})",
+			path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string())
+		);
+
+		if let Some(Error::OpenFailed(failures)) = &mut self.error {
+			failures.push(failure);
+		} else {
+			self.error = Some(Error::OpenFailed(vec![failure]));
+		}
 	}
 
-	fn theme(&self) -> Theme {
-		self.theme.clone()
+	/// Starts collecting the failures of a new drop, unless files of the last
+	/// one are still being opened.
+	fn start_drop(&mut self) {
+		if self.pending_drops == 0 && matches!(self.error, Some(Error::OpenFailed(_))) {
+			self.error = None;
+		}
+
+		self.pending_drops += 1;
+	}
+
+	/// Shows the folder in the sidebar, replacing the current workspace.
+	fn open_workspace(&mut self, root: PathBuf) -> Command<Message> {
+		self.workspace = Some(Workspace::new(root.clone()));
+		self.show_sidebar = true;
+
+		Command::batch([read_directory(root.clone()), index_workspace(root)])
+	}
+
+	/// Moves the path to the top of the recent files.
+	fn remember_file(&mut self, path: &Path) {
+		self.recent_files.retain(|recent| recent != path);
+		self.recent_files.insert(0, path.to_path_buf());
+		self.recent_files.truncate(MAX_RECENT_FILES);
+		self.missing_files.remove(path);
+
+		config::save(self);
+	}
+
+	fn shortcut(&self, action: Action) -> Option<String> {
+		keybindings::label(&self.keybindings, action)
+	}
+
+	/// Moves the cursor of the active file to the given line and column.
+	fn move_cursor(&mut self, position: (usize, usize)) {
+		let file = &mut self.files[self.current];
+
+		set_cursor(&mut file.content, position);
+		file.history.end_group();
+	}
+
+	fn close_request_indices(&self, request: &CloseRequest) -> Vec<usize> {
+		match request {
+			// Tabs closed in the meantime are left out
+			CloseRequest::Tabs(ids) => ids.iter().filter_map(|&id| self.file_index(id)).collect(),
+			CloseRequest::Window => (0..self.files.len()).collect(),
+		}
+	}
+
+	/// The open tabs and the files on disk that Replace All in the "Find in
+	/// Files" results changes.
+	fn replace_targets(&self) -> (Vec<usize>, Vec<PathBuf>) {
+		let mut tabs = Vec::new();
+		let mut on_disk = Vec::new();
+
+		for results in &self.find_in_files.results {
+			let tab = match &results.location {
+				find_in_files::Location::Tab(id) => self.file_index(*id),
+				find_in_files::Location::Path(path) => self.files
+					.iter()
+					.position(|file| file.path.as_ref() == Some(path)),
+			};
+
+			match (tab, &results.location) {
+				(Some(index), _) => tabs.push(index),
+				(None, find_in_files::Location::Path(path)) => on_disk.push(path.clone()),
+				// The tab was closed since the search
+				(None, find_in_files::Location::Tab(_)) => {}
+			}
+		}
+
+		(tabs, on_disk)
+	}
+
+	fn file_index(&self, id: FileId) -> Option<usize> {
+		self.files.iter().position(|file| file.id == id)
+	}
+
+	fn file(&self, id: FileId) -> Option<&File> {
+		self.files.iter().find(|file| file.id == id)
 	}
 }
 
-async fn pick_file() -> Result<(PathBuf, Arc<String>), Error> {
+async fn pick_file() -> Result<(PathBuf, Arc<String>, TextFormat), Error> {
 	let handle = rfd::AsyncFileDialog::new()
 		.set_title("Open File:")
 		.pick_file()
@@ -574,19 +3368,235 @@ async fn pick_file() -> Result<(PathBuf, Arc<String>), Error> {
 	load_file(handle.path()).await
 }
 
-async fn load_file(path: &Path) -> Result<(PathBuf, Arc<String>), Error> {
-	let contents = tokio::fs::read_to_string(&path)
+/// The files that don't exist anymore.
+fn missing_files(paths: &[PathBuf]) -> BTreeSet<PathBuf> {
+	paths
+		.iter()
+		.filter(|path| !path.is_file())
+		.cloned()
+		.collect()
+}
+
+/// Files larger than this are left out when opening every file in a folder.
+const MAX_OPEN_ALL_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Opens a file dropped onto the window, remembering its path in case it fails.
+fn open_dropped_file(path: PathBuf, text_only: bool) -> Command<Message> {
+	Command::perform(
+		{
+			let path = path.clone();
+
+			async move {
+				if text_only {
+					load_text_file(&path).await
+				} else {
+					load_file(&path).await
+				}
+			}
+		},
+		move |result| Message::DroppedFileOpened(path, result),
+	)
+}
+
+fn index_workspace(root: PathBuf) -> Command<Message> {
+	Command::perform(quick_open::index(root.clone()), move |files| {
+		Message::WorkspaceIndexed(root, files)
+	})
+}
+
+fn read_directory(directory: PathBuf) -> Command<Message> {
+	Command::perform(workspace::read_directory(directory.clone()), move |result| {
+		Message::DirectoryRead(directory, result)
+	})
+}
+
+async fn pick_folder() -> Option<PathBuf> {
+	rfd::AsyncFileDialog::new()
+		.set_title("Choose a Folder:")
+		.pick_folder()
+		.await
+		.map(|handle| handle.path().to_owned())
+}
+
+/// Restores the previous session and opens the files given on the command line.
+///
+/// Returns the files, the index of the active one, the tab strips of the
+/// panes, the focused one first, and the files that couldn't be opened.
+fn startup_files(flags: &cli::Args, session: Option<Session>) -> (Vec<File>, usize, Vec<Pane>, Vec<String>) {
+	let (mut files, mut current, mut panes) = session
+		.map(|session| {
+			let active = session.active;
+			let mut current = 0;
+			let mut files = Vec::new();
+			// Where each tab of the session ended up, as tabs that can't be
+			// restored are left out
+			let mut restored = Vec::new();
+
+			for (index, tab) in session.tabs.into_iter().enumerate() {
+				if index == active {
+					current = files.len();
+				}
+
+				let file = File::restore(tab);
+
+				restored.push(file.is_some().then_some(files.len()));
+				files.extend(file);
+			}
+
+			let current = current.min(files.len().saturating_sub(1));
+
+			let panes: Vec<Pane> = session.panes
+				.into_iter()
+				.filter_map(|pane| {
+					let tabs: Vec<usize> = pane.tabs
+						.iter()
+						.filter_map(|&tab| restored.get(tab).copied().flatten())
+						.collect();
+
+					let active = restored
+						.get(pane.active)
+						.copied()
+						.flatten()
+						.filter(|active| tabs.contains(active))
+						.or_else(|| tabs.first().copied())?;
+
+					Some(Pane::new(tabs, active))
+				})
+				.collect();
+
+			(files, current, panes)
+		})
+		.unwrap_or_default();
+
+	let mut errors = flags.errors.clone();
+
+	for argument in &flags.files {
+		if let Some(index) = files.iter().position(|file| file.path.as_ref() == Some(&argument.path)) {
+			let file = &mut files[index];
+
+			file.content = content_with_cursor(&buffer_text(&file.content), argument.position);
+			file.read_only |= flags.readonly;
+			current = index;
+
+			continue;
+		}
+
+		match std::fs::read(&argument.path) {
+			Ok(bytes) => {
+				let (text, format) = decode_file(&bytes, None);
+
+				files.push(File {
+					path: Some(argument.path.clone()),
+					content: content_with_cursor(&text, argument.position_in(&text)),
+					format,
+					read_only: flags.readonly,
+					modified_at: watcher::modified_time(&argument.path),
+					..File::empty()
+				});
+				current = files.len() - 1;
+			}
+			Err(error) => errors.push(format!("Failed to open \"{}\": {error}", argument.path.display())),
+		}
+	}
+
+	if files.is_empty() {
+		files.push(if flags.new_window || !flags.files.is_empty() {
+			File::empty()
+		} else {
+			File::sample()
+		});
+	}
+
+	// Sessions from before panes were saved, and files from the command line,
+	// go to the focused pane
+	if panes.is_empty() {
+		panes.push(Pane::new(Vec::new(), current));
+	}
+
+	for index in 0..files.len() {
+		if !panes.iter().any(|pane| pane.tabs.contains(&index)) {
+			panes[0].tabs.push(index);
+		}
+	}
+
+	for pane in &mut panes {
+		pane.keep_pinned_first(&files);
+	}
+
+	(files, current, panes, errors)
+}
+
+async fn load_file(path: &Path) -> Result<(PathBuf, Arc<String>, TextFormat), Error> {
+	load_file_with_encoding(path, None).await
+}
+
+/// Loads a file opened along with the rest of its folder, which is left out
+/// if it's too large or doesn't look like text.
+async fn load_text_file(path: &Path) -> Result<(PathBuf, Arc<String>, TextFormat), Error> {
+	let metadata = tokio::fs::metadata(path)
+		.await
+		.map_err(|error| Error::IOFailed(error.kind()))?;
+
+	if metadata.len() > MAX_OPEN_ALL_SIZE {
+		return Err(Error::IOFailed(io::ErrorKind::FileTooLarge));
+	}
+
+	let bytes = tokio::fs::read(path)
+		.await
+		.map_err(|error| Error::IOFailed(error.kind()))?;
+
+	if encoding::is_binary(&bytes) {
+		return Err(Error::NotText);
+	}
+
+	let (contents, format) = decode_file(&bytes, None);
+
+	Ok((PathBuf::from(path), Arc::new(contents), format))
+}
+
+async fn load_file_with_encoding(
+	path: &Path,
+	encoding: Option<Encoding>,
+) -> Result<(PathBuf, Arc<String>, TextFormat), Error> {
+	let bytes = tokio::fs::read(&path)
 		.await
-		.map(verify_content)
-		.map(Arc::new)
 		.map_err(|error| error.kind())
 		.map_err(Error::IOFailed)?;
 
-	Ok((PathBuf::from(path), contents))
+	let (contents, format) = decode_file(&bytes, encoding);
+
+	Ok((PathBuf::from(path), Arc::new(contents), format))
+}
+
+/// Decodes the contents of a file, detecting its encoding unless one is given.
+fn decode_file(bytes: &[u8], encoding: Option<Encoding>) -> (String, TextFormat) {
+	let (text, encoding, bom) = encoding::decode(bytes, encoding);
+	let (contents, mut format) = verify_content(text);
+
+	format.encoding = encoding;
+	format.bom = bom;
+
+	(contents, format)
 }
 
-async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error> {
+/// Writes the text to the path, or to one picked by the user if there is none.
+///
+/// Refuses to overwrite the file if it was modified on disk after the time the
+/// tab last saw, unless no time is given.
+async fn save_file(
+	path: Option<PathBuf>,
+	text: String,
+	format: TextFormat,
+	modified_at: Option<SystemTime>,
+	backup: Backup,
+) -> Result<PathBuf, Error> {
 	let path = if let Some(path) = path {
+		let on_disk = watcher::modified_time(&path);
+
+		if modified_at.is_some() && on_disk.is_some() && on_disk != modified_at {
+			return Err(Error::ChangedOnDisk);
+		}
+
 		path
 	} else {
 		rfd::AsyncFileDialog::new()
@@ -597,7 +3607,10 @@ async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error
 			.map(|handle| handle.path().to_owned())?
 	};
 
-	tokio::fs::write(&path, text)
+	let bytes = encoding::encode(&format.apply(&text), format.encoding, format.bom)
+		.ok_or(Error::EncodingFailed(format.encoding))?;
+
+	atomic_write::write(path.clone(), bytes, backup)
 		.await
 		.map_err(|error| Error::IOFailed(error.kind()))?;
 
@@ -608,12 +3621,218 @@ async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error
 enum Error {
 	DialogClosed,
 	IOFailed(io::ErrorKind),
+	EncodingFailed(Encoding),
+	SearchFailed(String),
+	InvalidKeybinding(String),
+	InvalidArgument(String),
+	ChangedOnDisk,
+	/// Files that couldn't be opened, with the reasons why.
+	OpenFailed(Vec<String>),
+	NotText,
+}
+
+/// The file name of a path, preceded by as many parent folders as it takes to
+/// tell it apart from the other paths with the same file name, like
+/// `editor/mod.rs` next to `config/mod.rs`.
+fn distinct_name<'a>(path: &Path, others: impl Iterator<Item = &'a Path>) -> String {
+	let components: Vec<&OsStr> = path.iter().collect();
+
+	let others: Vec<Vec<&OsStr>> = others
+		.filter(|other| *other != path && other.file_name() == path.file_name())
+		.map(|other| other.iter().collect())
+		.collect();
+
+	let mut depth = 1;
+
+	while depth < components.len()
+		&& others.iter().any(|other| other.ends_with(&components[components.len() - depth..]))
+	{
+		depth += 1;
+	}
+
+	components[components.len().saturating_sub(depth)..]
+		.iter()
+		.collect::<PathBuf>()
+		.display()
+		.to_string()
+}
+
+/// The text of the editor buffer. Unlike `Content::text`, this doesn't always
+/// end the text with a line break, which files without one would gain.
+fn buffer_text(content: &text_editor::Content) -> String {
+	let mut text = String::new();
+
+	for (index, line) in content.lines().enumerate() {
+		if index > 0 {
+			text.push('\n');
+		}
+
+		text.push_str(&line);
+	}
+
+	text
+}
+
+/// Moves the cursor to a line and byte column, keeping the scroll position of
+/// the content unless it has to be rebuilt, see [`move_cursor_to`].
+fn set_cursor(content: &mut text_editor::Content, position: (usize, usize)) {
+	if !move_cursor_to(content, position, false) {
+		*content = content_with_cursor(&buffer_text(content), position);
+	}
+}
+
+/// Moves the cursor to a line and byte column one motion at a time, selecting
+/// the text on the way if asked to, which keeps the scroll position.
+///
+/// Motions need the line under the cursor to be laid out, which it isn't in
+/// content that was never shown or right after an edit. Returns `false`
+/// without moving the cursor then.
+fn move_cursor_to(content: &mut text_editor::Content, (line, column): (usize, usize), select: bool) -> bool {
+	use text_editor::{Action, Motion};
+
+	// Moving collapses a selection without looking at the layout
+	if !select && content.selection().is_some() {
+		content.perform(Action::Move(Motion::Left));
+	}
+
+	let mut step = |motion| {
+		let before = content.cursor_position();
+
+		content.perform(if select { Action::Select(motion) } else { Action::Move(motion) });
+
+		let after = content.cursor_position();
+
+		(after != before).then_some(after)
+	};
+
+	// Moving sideways does nothing on a line that isn't laid out, so going
+	// there and back tells whether it is
+	let laid_out = step(Motion::Right)
+		.and_then(|_| step(Motion::Left))
+		.or_else(|| step(Motion::Left).and_then(|_| step(Motion::Right)));
+
+	let Some(mut position) = laid_out else {
+		return false;
+	};
+
+	// Wrapped lines take several motions, so the cursor moves until it
+	// reaches the line rather than a number of times
+	while position.0 > line {
+		let Some(next) = step(Motion::Up) else { break };
+		position = next;
+	}
+
+	while position.0 < line {
+		let Some(next) = step(Motion::Down) else { break };
+		position = next;
+	}
+
+	while position.0 == line && position.1 > column {
+		let Some(next) = step(Motion::Left) else { break };
+		position = next;
+	}
+
+	while position.0 == line && position.1 < column {
+		let Some(next) = step(Motion::Right) else { break };
+		position = next;
+	}
+
+	true
 }
 
+/// Creates editor content with the cursor placed at the given line and column.
+///
+/// `Content` has no way to move the cursor to an arbitrary position, so the
+/// text before the cursor is pasted in front of the rest, which leaves the
+/// cursor right after it. A placeholder character is pasted along with it and
+/// removed again, since pasting drops a trailing control character like a tab.
+fn content_with_cursor(text: &str, (line, column): (usize, usize)) -> text_editor::Content {
+	let mut offset = 0;
+
+	for (index, current) in text.split('\n').enumerate() {
+		if index == line {
+			let mut column = column.min(current.len());
+
+			while !current.is_char_boundary(column) {
+				column -= 1;
+			}
+
+			offset += column;
+			break;
+		}
+
+		offset += current.len() + 1;
+	}
+
+	let offset = offset.min(text.len());
+
+	let mut content = text_editor::Content::with_text(&text[offset..]);
+
+	content.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+		format!("{}_", &text[..offset]),
+	))));
+	content.perform(text_editor::Action::Edit(text_editor::Edit::Backspace));
+
+	content
+}
+
+/// Normalizes line endings to `'\n'` for the editor buffer, returning the
+/// format the text had on disk so it can be restored when saving.
 #[allow(clippy::needless_pass_by_value)]
-fn verify_content(string: String) -> String {
-	string
-		.replace('\t', "    ")
+fn verify_content(string: String) -> (String, TextFormat) {
+	let format = TextFormat::detect(&string);
+
+	let string = string
 		.replace("\r\n", "\n")
-		.replace('\r', "\n")
-}
\ No newline at end of file
+		.replace('\r', "\n");
+
+	(string, format)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn set_cursor_in_content_that_was_never_shown() {
+		let mut content = text_editor::Content::with_text("ab\ncd\u{e9} f\n\nghij");
+
+		assert!(!move_cursor_to(&mut content, (1, 5), false));
+
+		set_cursor(&mut content, (1, 5));
+
+		assert_eq!(content.cursor_position(), (1, 5));
+		assert_eq!(buffer_text(&content), "ab\ncd\u{e9} f\n\nghij");
+	}
+
+	fn name(path: &str, others: &[&str]) -> String {
+		distinct_name(Path::new(path), others.iter().map(Path::new))
+	}
+
+	#[test]
+	fn distinct_name_without_collisions() {
+		assert_eq!(name("/project/src/main.rs", &["/project/src/lib.rs"]), "main.rs");
+		assert_eq!(name("/project/src/main.rs", &["/project/src/main.rs"]), "main.rs");
+	}
+
+	#[test]
+	fn distinct_name_with_shared_parents() {
+		assert_eq!(name("/project/editor/mod.rs", &["/project/config/mod.rs"]), "editor/mod.rs");
+		assert_eq!(name("/one/src/lib.rs", &["/two/src/lib.rs"]), "one/src/lib.rs");
+	}
+
+	#[test]
+	fn distinct_name_at_the_root() {
+		assert_eq!(name("/mod.rs", &["/editor/mod.rs"]), "/mod.rs");
+		assert_eq!(name("/editor/mod.rs", &["/mod.rs"]), "editor/mod.rs");
+	}
+
+	#[test]
+	fn distinct_name_with_nested_collisions() {
+		let paths = ["/project/a/b/mod.rs", "/project/b/mod.rs", "/project/c/mod.rs"];
+
+		assert_eq!(name(paths[0], &paths), "a/b/mod.rs");
+		assert_eq!(name(paths[1], &paths), "project/b/mod.rs");
+		assert_eq!(name(paths[2], &paths), "c/mod.rs");
+	}
+}
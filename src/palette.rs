@@ -0,0 +1,225 @@
+use std::sync::LazyLock;
+
+use iced::highlighter;
+use iced::widget::text_input;
+
+use crate::encoding::Encoding;
+use crate::format::LineEnding;
+use crate::keybindings::Action;
+use crate::{CloseScope, Editor, Message, ModalType, THEMES};
+
+pub static INPUT_ID: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("palette-input"));
+
+/// How many recently used commands are remembered.
+const MAX_RECENT: usize = 10;
+
+/// How many commands are shown at once.
+pub const MAX_VISIBLE: usize = 12;
+
+/// The state of the command palette.
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+	pub query: String,
+	pub selected: usize,
+	/// The labels of the recently used commands, most recent first.
+	pub recent: Vec<String>,
+}
+
+impl Palette {
+	pub fn remember(&mut self, label: &str) {
+		self.recent.retain(|recent| recent != label);
+		self.recent.insert(0, label.to_string());
+		self.recent.truncate(MAX_RECENT);
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+	pub label: String,
+	pub shortcut: Option<String>,
+	pub message: Message,
+}
+
+impl Entry {
+	fn new(label: impl Into<String>, message: Message) -> Self {
+		Entry {
+			label: label.into(),
+			shortcut: None,
+			message,
+		}
+	}
+}
+
+/// Lists every command that can currently be run from the palette.
+pub fn entries(state: &Editor) -> Vec<Entry> {
+	let file = &state.files[state.current];
+
+	let mut entries: Vec<Entry> = Action::ALL
+		.into_iter()
+		.filter(|action| !matches!(action, Action::Dismiss | Action::CommandPalette))
+		.map(|action| Entry {
+			label: action.to_string(),
+			shortcut: state.shortcut(action),
+			message: action.message(),
+		})
+		.collect();
+
+	entries.extend([
+		Entry::new(if file.pinned { "Unpin Tab" } else { "Pin Tab" }, Message::TogglePin(state.current)),
+		Entry::new("Close Other Tabs", Message::CloseMany(CloseScope::Others(state.current))),
+		Entry::new("Close Tabs to the Right", Message::CloseMany(CloseScope::ToTheRight(state.focus, state.current))),
+		Entry::new("Close Saved Tabs", Message::CloseMany(CloseScope::Saved)),
+		Entry::new("Close All Tabs", Message::CloseMany(CloseScope::All)),
+		Entry::new("Change Encoding", Message::ShowModal(ModalType::Encoding)),
+		Entry::new("About", Message::ShowModal(ModalType::About)),
+	]);
+
+	if let Some(path) = &file.path {
+		entries.push(Entry::new("Show in Explorer", Message::ShowInExplorer(path.clone())));
+	}
+
+	entries.extend(
+		state.recent_files
+			.iter()
+			.filter(|path| !state.missing_files.contains(*path))
+			.map(|path| Entry::new(format!("Open Recent: {}", path.display()), Message::OpenPath(path.clone())))
+	);
+
+	if state.workspace.is_some() {
+		entries.push(Entry::new("Close Folder", Message::CloseFolder));
+	}
+
+	if !state.recent_files.is_empty() {
+		entries.push(Entry::new("Clear Recent Files", Message::ClearRecentFiles));
+	}
+
+	entries.extend(
+		THEMES
+			.iter()
+			.map(|theme| Entry::new(format!("Theme: {theme}"), Message::SelectTheme(theme.clone())))
+	);
+
+	entries.extend(
+		highlighter::Theme::ALL
+			.iter()
+			.map(|theme| Entry::new(format!("Syntax Theme: {theme}"), Message::SelectSyntaxTheme(*theme)))
+	);
+
+	entries.extend(
+		LineEnding::ALL
+			.iter()
+			.map(|line_ending| {
+				Entry::new(format!("Line Endings: {line_ending}"), Message::SetLineEnding(*line_ending))
+			})
+	);
+
+	if file.path.is_some() {
+		entries.extend(
+			Encoding::ALL
+				.iter()
+				.map(|encoding| {
+					Entry::new(format!("Reopen with Encoding: {encoding}"), Message::ReopenWithEncoding(*encoding))
+				})
+		);
+	}
+
+	entries.extend(
+		Encoding::ALL
+			.iter()
+			.map(|encoding| {
+				Entry::new(format!("Save with Encoding: {encoding}"), Message::SaveWithEncoding(*encoding))
+			})
+	);
+
+	if file.format.encoding.is_unicode() {
+		entries.push(if file.format.bom {
+			Entry::new("Remove Byte Order Mark", Message::SetBom(false))
+		} else {
+			Entry::new("Add Byte Order Mark", Message::SetBom(true))
+		});
+	}
+
+	entries
+}
+
+/// Keeps the entries matching the query, with recently used ones first and
+/// the rest sorted by how well they match.
+pub fn filter(entries: Vec<Entry>, query: &str, recent: &[String]) -> Vec<Entry> {
+	let mut matching: Vec<(Entry, i32, usize)> = entries
+		.into_iter()
+		.filter_map(|entry| {
+			let score = score(query, &entry.label)?;
+			let recency = recent
+				.iter()
+				.position(|label| *label == entry.label)
+				.unwrap_or(usize::MAX);
+
+			Some((entry, score, recency))
+		})
+		.collect();
+
+	// Shorter labels win ties, since more of them was matched
+	matching.sort_by(|(a, a_score, a_recency), (b, b_score, b_recency)| {
+		a_recency
+			.cmp(b_recency)
+			.then(b_score.cmp(a_score))
+			.then(a.label.len().cmp(&b.label.len()))
+	});
+
+	matching.into_iter().map(|(entry, _, _)| entry).collect()
+}
+
+/// Scores how well a label matches a query, which matches if all of its
+/// characters appear in the label in order.
+///
+/// Consecutive characters and characters at the start of words score higher,
+/// while gaps between them score lower.
+pub fn score(query: &str, label: &str) -> Option<i32> {
+	let label: Vec<char> = label.chars().collect();
+
+	let mut score = 0;
+	let mut position = 0;
+
+	for character in query.chars().filter(|character| !character.is_whitespace()) {
+		let index = (position..label.len())
+			.find(|&index| eq_ignore_case(label[index], character))?;
+
+		if position > 0 && index == position {
+			score += 5;
+		}
+
+		if index == 0 || !label[index - 1].is_alphanumeric() {
+			score += 3;
+		}
+
+		score -= i32::try_from(index - position).unwrap_or(i32::MAX).min(10);
+		position = index + 1;
+	}
+
+	Some(score)
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+	a.to_lowercase().eq(b.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn score_needs_every_character_in_order() {
+		assert!(score("sv", "Save").is_some());
+		assert!(score("SAVE", "save as").is_some());
+		assert!(score("vs", "Save").is_none());
+		assert!(score("x", "Save").is_none());
+		assert_eq!(score("", "Save"), Some(0));
+	}
+
+	#[test]
+	fn score_prefers_consecutive_characters_and_word_starts() {
+		assert!(score("sav", "Save File") > score("sav", "Search And View"));
+		assert!(score("sa", "Save") > score("sa", "Stay"));
+		assert!(score("sf", "Save File") > score("sf", "Sort Buffers"));
+	}
+}
@@ -0,0 +1,145 @@
+use iced::widget::scrollable::{self, RelativeOffset};
+use iced::widget::text_editor;
+use iced::Command;
+
+use crate::history::Change;
+use crate::search::LineIndex;
+use crate::{buffer_text, content_with_cursor, File};
+
+/// A pane of the editor, with its own tab strip.
+pub struct Pane {
+	/// The indices of the files shown as tabs.
+	pub tabs: Vec<usize>,
+	/// The index of the file shown in the pane.
+	pub active: usize,
+	/// The pane's own cursor and scroll position while another pane shows the
+	/// same file, since the content of a file only has one of each.
+	pub view: Option<View>,
+	pub tab_strip: scrollable::Id,
+	/// How far the tab strip is scrolled, from 0 at the first tab to 1 at the
+	/// last one.
+	pub tab_scroll: f32,
+}
+
+/// A copy of the content of a file, shown by a pane that doesn't have the
+/// focus. It's brought up to date whenever the text of the file changes.
+pub struct View {
+	pub content: text_editor::Content,
+	/// The revision of the file's text the copy has.
+	pub revision: u64,
+}
+
+impl View {
+	pub fn new(file: &File) -> Self {
+		View {
+			content: content_with_cursor(&buffer_text(&file.content), file.content.cursor_position()),
+			revision: file.revision,
+		}
+	}
+
+	/// Copies the file's current text, moving the cursor along with the
+	/// changes made since the last copy.
+	pub fn refresh(&mut self, file: &File) {
+		if self.revision == file.revision {
+			return;
+		}
+
+		let copied = buffer_text(&self.content);
+		let text = buffer_text(&file.content);
+
+		let cursor = LineIndex::new(&copied)
+			.offset(self.content.cursor_position())
+			.min(copied.len());
+		let cursor = Change::between(&copied, &text).shift(cursor);
+
+		self.content = content_with_cursor(&text, LineIndex::new(&text).position(cursor));
+		self.revision = file.revision;
+	}
+
+	/// Turns the copy into content with the file's current text.
+	pub fn into_content(mut self, file: &File) -> text_editor::Content {
+		self.refresh(file);
+
+		self.content
+	}
+}
+
+impl Pane {
+	pub fn new(tabs: Vec<usize>, active: usize) -> Self {
+		Pane {
+			tabs,
+			active,
+			view: None,
+			tab_strip: scrollable::Id::unique(),
+			tab_scroll: 0.0,
+		}
+	}
+
+	/// Moves a tab to where another tab is, shifting the tabs in between.
+	pub fn move_tab(&mut self, index: usize, target: usize) {
+		let from = self.tabs.iter().position(|&tab| tab == index);
+		let to = self.tabs.iter().position(|&tab| tab == target);
+
+		if let (Some(from), Some(to)) = (from, to) {
+			let tab = self.tabs.remove(from);
+
+			self.tabs.insert(to, tab);
+		}
+	}
+
+	/// Moves pinned tabs to the start of the strip, keeping the order of the others.
+	pub fn keep_pinned_first(&mut self, files: &[File]) {
+		self.tabs.sort_by_key(|&tab| !files[tab].pinned);
+	}
+
+	pub fn scroll_tabs<Message: 'static>(&mut self, offset: f32) -> Command<Message> {
+		self.tab_scroll = offset.clamp(0.0, 1.0);
+
+		scrollable::snap_to(self.tab_strip.clone(), RelativeOffset { x: self.tab_scroll, y: 0.0 })
+	}
+
+	/// How far to scroll the tab strip to move by one tab.
+	#[allow(clippy::cast_precision_loss)]
+	pub fn tab_step(&self) -> f32 {
+		1.0 / self.tabs.len().saturating_sub(1).max(1) as f32
+	}
+
+	/// Scrolls the tab strip so the active tab is visible. Scrolling to the
+	/// tab's share of the strip always shows it, whatever the strip's width.
+	#[allow(clippy::cast_precision_loss)]
+	pub fn reveal_active<Message: 'static>(&mut self) -> Command<Message> {
+		let position = self.tabs.iter().position(|&tab| tab == self.active).unwrap_or(0);
+
+		self.scroll_tabs(position as f32 * self.tab_step())
+	}
+
+	/// Removes a tab, making the tab next to it active if it was the active one.
+	pub fn remove_tab(&mut self, index: usize) {
+		let Some(position) = self.tabs.iter().position(|&tab| tab == index) else {
+			return;
+		};
+
+		self.tabs.remove(position);
+
+		if self.active == index {
+			if let Some(&next) = self.tabs.get(position).or_else(|| self.tabs.last()) {
+				self.active = next;
+			}
+
+			self.view = None;
+		}
+	}
+
+	/// Updates the indices after the file at the given index was closed.
+	pub fn file_closed(&mut self, index: usize) {
+		self.remove_tab(index);
+
+		let shift = |tab: usize| if tab > index { tab - 1 } else { tab };
+
+		for tab in &mut self.tabs {
+			*tab = shift(*tab);
+		}
+
+		self.active = shift(self.active);
+	}
+}
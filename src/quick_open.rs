@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use iced::widget::text_input;
+use ignore::WalkBuilder;
+
+use crate::{palette, Editor};
+
+pub static INPUT_ID: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("quick-open-input"));
+
+/// Indexing stops after this many files, to keep huge folders usable.
+const MAX_FILES: usize = 100_000;
+
+/// Added to the score of the most recently opened file, and one less for each
+/// file opened before it.
+const RECENT_BONUS: i32 = 20;
+
+/// The state of the quick open overlay.
+#[derive(Debug, Clone, Default)]
+pub struct QuickOpen {
+	pub query: String,
+	pub selected: usize,
+	/// The matches of the query, updated when it or the files change.
+	pub matches: Vec<Match>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Match {
+	pub path: PathBuf,
+	/// The path relative to the workspace.
+	pub label: String,
+	pub recent: bool,
+}
+
+/// Lists the files under the folder, leaving out the ones ignored by
+/// `.gitignore` and similar files.
+pub async fn index(root: PathBuf) -> Vec<PathBuf> {
+	tokio::task::spawn_blocking(move || {
+		WalkBuilder::new(&root)
+			.hidden(false)
+			.require_git(false)
+			.filter_entry(|entry| entry.file_name() != ".git")
+			.build()
+			.filter_map(Result::ok)
+			.filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+			.take(MAX_FILES)
+			.map(ignore::DirEntry::into_path)
+			.collect()
+	})
+		.await
+		.unwrap_or_default()
+}
+
+/// The files of the workspace matching the query, best matches first. Without
+/// a workspace, the recently opened files are searched instead.
+pub fn matches(state: &Editor) -> Vec<Match> {
+	let root = state.workspace.as_ref().map(|workspace| &workspace.root);
+
+	let candidates = match &state.workspace {
+		Some(workspace) => &workspace.files,
+		None => &state.recent_files,
+	};
+
+	let recent: HashMap<&PathBuf, usize> = state.recent_files
+		.iter()
+		.enumerate()
+		.map(|(position, path)| (path, position))
+		.collect();
+
+	let mut matching: Vec<(Match, i32)> = candidates
+		.iter()
+		.filter_map(|path| {
+			let label = root
+				.and_then(|root| path.strip_prefix(root).ok())
+				.unwrap_or(path)
+				.display()
+				.to_string();
+
+			let mut score = palette::score(&state.quick_open.query, &label)?;
+
+			let recency = recent.get(path).copied();
+
+			if let Some(position) = recency {
+				score += RECENT_BONUS - i32::try_from(position).unwrap_or(RECENT_BONUS);
+			}
+
+			Some((
+				Match {
+					path: path.clone(),
+					label,
+					recent: recency.is_some(),
+				},
+				score,
+			))
+		})
+		.collect();
+
+	// Shorter paths win ties, since more of them was matched
+	matching.sort_by(|(a, a_score), (b, b_score)| {
+		b_score
+			.cmp(a_score)
+			.then(a.label.len().cmp(&b.label.len()))
+			.then_with(|| a.label.cmp(&b.label))
+	});
+
+	matching.into_iter().map(|(found, _)| found).collect()
+}
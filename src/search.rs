@@ -0,0 +1,235 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use iced::widget::text_input;
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::{File, FileId};
+
+pub static QUERY_ID: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("search-query"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+	pub regex: bool,
+	pub case_sensitive: bool,
+	pub whole_word: bool,
+}
+
+/// The state of the find and replace bar.
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+	pub visible: bool,
+	pub query: String,
+	pub replacement: String,
+	pub options: Options,
+	/// The matches in the current file, found again whenever the query or
+	/// the text changes.
+	pub matches: Vec<Range<usize>>,
+	pub error: Option<regex::Error>,
+	pub lines: LineIndex,
+	/// The file and the revision of its text the matches were found in.
+	pub searched: Option<(FileId, u64)>,
+}
+
+impl Search {
+	/// Compiles the query, returning `None` if it's empty.
+	pub fn regex(&self) -> Option<Result<Regex, regex::Error>> {
+		if self.query.is_empty() {
+			return None;
+		}
+
+		let pattern = if self.options.regex {
+			self.query.clone()
+		} else {
+			regex::escape(&self.query)
+		};
+
+		let pattern = if self.options.whole_word {
+			format!(r"\b(?:{pattern})\b")
+		} else {
+			pattern
+		};
+
+		Some(
+			RegexBuilder::new(&pattern)
+				.case_insensitive(!self.options.case_sensitive)
+				.multi_line(true)
+				.build()
+		)
+	}
+
+	/// Finds the matches in the file again, unless they were found in the same
+	/// text already.
+	pub fn refresh(&mut self, file: &File) {
+		let searched = Some((file.id, file.revision));
+
+		if self.searched == searched {
+			return;
+		}
+
+		let text = crate::buffer_text(&file.content);
+
+		(self.matches, self.error) = match self.find(&text) {
+			Ok(matches) => (matches, None),
+			Err(error) => (Vec::new(), Some(error)),
+		};
+		self.lines = LineIndex::new(&text);
+		self.searched = searched;
+	}
+
+	/// Finds the byte ranges of all non-empty matches of the query in the given text.
+	pub fn find(&self, text: &str) -> Result<Vec<Range<usize>>, regex::Error> {
+		let Some(regex) = self.regex() else {
+			return Ok(Vec::new());
+		};
+
+		Ok(regex?
+			.find_iter(text)
+			.filter(|found| !found.is_empty())
+			.map(|found| found.range())
+			.collect())
+	}
+
+	/// Expands the replacement for a match, substituting capture groups when
+	/// searching with a regular expression.
+	pub fn expand(&self, captures: &Captures) -> String {
+		if self.options.regex {
+			let mut replacement = String::new();
+
+			captures.expand(&self.replacement, &mut replacement);
+
+			replacement
+		} else {
+			self.replacement.clone()
+		}
+	}
+
+	/// Replaces every match in the given text, returning the new text and the
+	/// amount of replacements made.
+	pub fn replace_all(&self, text: &str) -> Result<(String, usize), regex::Error> {
+		let Some(regex) = self.regex() else {
+			return Ok((text.to_string(), 0));
+		};
+
+		let mut replaced = String::with_capacity(text.len());
+		let mut last = 0;
+		let mut count = 0;
+
+		for captures in regex?.captures_iter(text) {
+			let found = captures.get(0).expect("The whole match is always captured");
+
+			if found.is_empty() {
+				continue;
+			}
+
+			replaced.push_str(&text[last..found.start()]);
+			replaced.push_str(&self.expand(&captures));
+			last = found.end();
+			count += 1;
+		}
+
+		replaced.push_str(&text[last..]);
+
+		Ok((replaced, count))
+	}
+}
+
+/// Maps byte offsets in a text to line and column positions.
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+	starts: Vec<usize>,
+}
+
+impl LineIndex {
+	pub fn new(text: &str) -> Self {
+		let starts = std::iter::once(0)
+			.chain(text.match_indices('\n').map(|(index, _)| index + 1))
+			.collect();
+
+		LineIndex { starts }
+	}
+
+	pub fn position(&self, offset: usize) -> (usize, usize) {
+		let line = self.starts.partition_point(|&start| start <= offset) - 1;
+
+		(line, offset - self.starts[line])
+	}
+
+	pub fn offset(&self, (line, column): (usize, usize)) -> usize {
+		self.starts.get(line).map_or(usize::MAX, |start| start + column)
+	}
+
+	/// Splits a range of the text into the column ranges it covers on each line.
+	pub fn line_ranges(&self, range: &Range<usize>) -> Vec<(usize, Range<usize>)> {
+		let (first, _) = self.position(range.start);
+		let (last, _) = self.position(range.end.saturating_sub(1).max(range.start));
+
+		(first..=last)
+			.map(|line| {
+				let start = range.start.max(self.starts[line]) - self.starts[line];
+				let end = self.starts
+					.get(line + 1)
+					.map_or(range.end, |&next| range.end.min(next - 1))
+					- self.starts[line];
+
+				(line, start..end.max(start))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn line_index_converts_offsets_and_positions() {
+		let index = LineIndex::new("one\ntwo\n\nfour");
+
+		assert_eq!(index.position(0), (0, 0));
+		assert_eq!(index.position(3), (0, 3));
+		assert_eq!(index.position(4), (1, 0));
+		assert_eq!(index.position(9), (3, 0));
+		assert_eq!(index.position(12), (3, 3));
+
+		assert_eq!(index.offset((1, 2)), 6);
+		assert_eq!(index.offset((3, 0)), 9);
+		assert_eq!(index.offset((4, 0)), usize::MAX);
+	}
+
+	#[test]
+	fn line_index_splits_ranges_into_lines() {
+		let index = LineIndex::new("one\ntwo\nthree");
+
+		assert_eq!(index.line_ranges(&(1..3)), [(0, 1..3)]);
+		assert_eq!(index.line_ranges(&(2..9)), [(0, 2..3), (1, 0..3), (2, 0..1)]);
+		// A range ending with a line break doesn't reach into the next line
+		assert_eq!(index.line_ranges(&(4..8)), [(1, 0..3)]);
+	}
+
+	#[test]
+	fn find_skips_empty_matches() {
+		let search = Search {
+			query: "o*".to_string(),
+			options: Options { regex: true, ..Options::default() },
+			..Search::default()
+		};
+
+		assert_eq!(search.find("foo boo").map_err(|error| error.to_string()), Ok(vec![1..3, 5..7]));
+	}
+
+	#[test]
+	fn replace_all_skips_empty_matches() {
+		let search = Search {
+			query: "(o*)".to_string(),
+			replacement: "<$1>".to_string(),
+			options: Options { regex: true, ..Options::default() },
+			..Search::default()
+		};
+
+		assert_eq!(
+			search.replace_all("foo boo").map_err(|error| error.to_string()),
+			Ok(("f<oo> b<oo>".to_string(), 2))
+		);
+	}
+}
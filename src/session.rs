@@ -0,0 +1,144 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CONFIG_PATH;
+use crate::format::TextFormat;
+use crate::{Editor, File};
+
+/// How often the session is written to disk, so that little is lost if the
+/// editor crashes.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+pub static SESSION_PATH: LazyLock<PathBuf> =
+	LazyLock::new(|| CONFIG_PATH.with_file_name("multi_tab_text_editor_session.json"));
+
+static NEXT_WRITE: AtomicU64 = AtomicU64::new(1);
+
+/// The number of the last write, held while writing so writes don't overlap.
+static LAST_WRITE: Mutex<u64> = Mutex::new(0);
+
+/// The open tabs, saved when the editor closes and restored when it starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+	pub tabs: Vec<Tab>,
+	pub active: usize,
+	/// The folder open in the sidebar.
+	#[serde(default)]
+	pub workspace: Option<PathBuf>,
+	/// The tab strips of the panes, the focused one first.
+	#[serde(default)]
+	pub panes: Vec<PaneTabs>,
+}
+
+/// The tabs of a pane, as indices into the tabs of the session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaneTabs {
+	pub tabs: Vec<usize>,
+	pub active: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tab {
+	pub path: Option<PathBuf>,
+	pub cursor: (usize, usize),
+	pub format: TextFormat,
+	/// The text of a tab with changes that aren't on disk.
+	#[serde(default)]
+	pub unsaved: Option<String>,
+	#[serde(default)]
+	pub read_only: bool,
+	#[serde(default)]
+	pub pinned: bool,
+}
+
+impl Tab {
+	pub fn capture(file: &File) -> Self {
+		Tab {
+			path: file.path.clone(),
+			cursor: file.content.cursor_position(),
+			format: file.format,
+			unsaved: file.is_modified.then(|| crate::buffer_text(&file.content)),
+			read_only: file.read_only,
+			pinned: file.pinned,
+		}
+	}
+}
+
+impl Session {
+	pub fn capture(state: &Editor) -> Self {
+		Session {
+			tabs: state.files
+				.iter()
+				.map(Tab::capture)
+				.collect(),
+			active: state.current,
+			workspace: state.workspace.as_ref().map(|workspace| workspace.root.clone()),
+			panes: state.panes
+				.get(state.focus)
+				.into_iter()
+				.chain(state.panes.iter().filter(|(pane, _)| **pane != state.focus).map(|(_, pane)| pane))
+				.map(|pane| PaneTabs {
+					tabs: pane.tabs.clone(),
+					active: pane.active,
+				})
+				.collect(),
+		}
+	}
+}
+
+pub fn load() -> Option<Session> {
+	let session_path = &*SESSION_PATH;
+
+	if !session_path.exists() {
+		return None;
+	}
+
+	std::fs::read_to_string(session_path)
+		.map_err(|error| error.to_string())
+		.and_then(|session| serde_json::from_str(&session).map_err(|error| error.to_string()))
+		.map_err(|error| eprintln!("Failed to read session file: {error}"))
+		.ok()
+}
+
+/// Writes the session in the background. Writes are numbered when they are
+/// started, so that a slow write never replaces a newer session.
+pub fn save(session: Session) -> impl Future<Output = ()> {
+	let number = NEXT_WRITE.fetch_add(1, Ordering::Relaxed);
+
+	async move {
+		let _ = tokio::task::spawn_blocking(move || write(&session, number)).await;
+	}
+}
+
+/// Writes the session before returning, e.g. when the editor closes.
+pub fn save_now(session: &Session) {
+	write(session, NEXT_WRITE.fetch_add(1, Ordering::Relaxed));
+}
+
+/// Writes the session to a temporary file first, so that a crash while saving
+/// doesn't leave a broken session behind.
+fn write(session: &Session, number: u64) {
+	let mut last_write = LAST_WRITE.lock().unwrap_or_else(PoisonError::into_inner);
+
+	if *last_write > number {
+		return;
+	}
+
+	let session_path = &*SESSION_PATH;
+	let temporary_path = session_path.with_extension("json.tmp");
+
+	let result = serde_json::to_string(session)
+		.map_err(std::io::Error::from)
+		.and_then(|session| std::fs::write(&temporary_path, session))
+		.and_then(|()| std::fs::rename(&temporary_path, session_path));
+
+	match result {
+		Ok(()) => *last_write = number,
+		Err(error) => eprintln!("Failed to write session to file: {error}"),
+	}
+}
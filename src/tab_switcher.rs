@@ -0,0 +1,81 @@
+use std::cmp::Reverse;
+
+use iced::keyboard::Modifiers;
+
+use crate::File;
+
+/// The state of the overlay shown while switching tabs with Ctrl+Tab.
+#[derive(Debug, Clone, Default)]
+pub struct TabSwitcher {
+	/// The indices of the files, the most recently used first.
+	pub tabs: Vec<usize>,
+	pub selected: usize,
+}
+
+impl TabSwitcher {
+	pub fn new(files: &[File]) -> Self {
+		let mut tabs: Vec<usize> = (0..files.len()).collect();
+
+		// Tabs that were never shown keep their order, after the others
+		tabs.sort_by_key(|&index| Reverse(files[index].activated_at));
+
+		TabSwitcher { tabs, selected: 0 }
+	}
+
+	/// Moves the selection to the next or previous tab, wrapping around.
+	pub fn cycle(&mut self, forward: bool) {
+		let count = self.tabs.len();
+
+		if count == 0 {
+			return;
+		}
+
+		self.selected = if forward {
+			(self.selected + 1) % count
+		} else {
+			(self.selected + count - 1) % count
+		};
+	}
+
+	pub fn selected_tab(&self) -> Option<usize> {
+		self.tabs.get(self.selected).copied()
+	}
+}
+
+/// Whether the keys that keep the switcher open are still held down. Shift
+/// only changes the direction, so releasing the others finishes the switch.
+pub fn is_held(modifiers: Modifiers) -> bool {
+	modifiers.control() || modifiers.alt() || modifiers.logo()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cycle_wraps_around() {
+		let mut switcher = TabSwitcher {
+			tabs: vec![2, 0, 1],
+			selected: 0,
+		};
+
+		switcher.cycle(false);
+		assert_eq!(switcher.selected_tab(), Some(1));
+
+		switcher.cycle(true);
+		switcher.cycle(true);
+		assert_eq!(switcher.selected_tab(), Some(0));
+
+		switcher.cycle(true);
+		assert_eq!(switcher.selected_tab(), Some(1));
+	}
+
+	#[test]
+	fn cycle_without_tabs() {
+		let mut switcher = TabSwitcher::default();
+
+		switcher.cycle(true);
+
+		assert_eq!(switcher.selected_tab(), None);
+	}
+}
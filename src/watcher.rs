@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use iced::futures::SinkExt;
+use iced::Subscription;
+use notify::{RecursiveMode, Watcher};
+
+use crate::format::TextFormat;
+use crate::Message;
+
+/// How long to wait for more changes after one comes in, since saving a file
+/// usually causes a burst of them.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// What an open file looks like on disk after something changed it.
+#[derive(Debug, Clone)]
+pub enum DiskState {
+	Present {
+		text: Arc<String>,
+		format: TextFormat,
+		modified_at: Option<SystemTime>,
+	},
+	Deleted,
+}
+
+/// Why a tab's buffer and its file on disk no longer agree.
+#[derive(Debug, Clone)]
+pub enum Conflict {
+	/// Another program changed the file while the tab had unsaved changes.
+	Changed {
+		text: Arc<String>,
+		format: TextFormat,
+		modified_at: Option<SystemTime>,
+	},
+	Deleted,
+}
+
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+	std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Reads the file again, returning `None` if it exists but can't be read.
+pub async fn read(path: PathBuf) -> Option<DiskState> {
+	match tokio::fs::read(&path).await {
+		Ok(bytes) => {
+			let (text, format) = crate::decode_file(&bytes, None);
+
+			Some(DiskState::Present {
+				text: Arc::new(text),
+				format,
+				modified_at: modified_time(&path),
+			})
+		}
+		Err(error) if error.kind() == std::io::ErrorKind::NotFound => Some(DiskState::Deleted),
+		Err(_) => None,
+	}
+}
+
+/// Watches the given files for changes made by other programs.
+///
+/// The folders containing the files are watched rather than the files
+/// themselves, so that files replaced by a rename are still noticed.
+pub fn watch(paths: Vec<PathBuf>) -> Subscription<Message> {
+	struct FileWatcher;
+
+	let paths: BTreeSet<PathBuf> = paths.into_iter().collect();
+
+	iced::subscription::channel(
+		(std::any::TypeId::of::<FileWatcher>(), paths.clone()),
+		100,
+		|mut output| async move {
+			let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+			let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+				if let Ok(event) = event {
+					for path in event.paths {
+						let _ = sender.send(path);
+					}
+				}
+			});
+
+			let _watcher = match watcher {
+				Ok(mut watcher) => {
+					let folders: BTreeSet<&Path> = paths.iter().filter_map(|path| path.parent()).collect();
+
+					for folder in folders {
+						if let Err(error) = watcher.watch(folder, RecursiveMode::NonRecursive) {
+							eprintln!("Failed to watch {}: {error}", folder.display());
+						}
+					}
+
+					Some(watcher)
+				}
+				Err(error) => {
+					eprintln!("Failed to watch open files: {error}");
+					None
+				}
+			};
+
+			loop {
+				let Some(path) = receiver.recv().await else {
+					std::future::pending::<()>().await;
+					continue;
+				};
+
+				let mut changed = BTreeSet::from([path]);
+
+				tokio::time::sleep(DEBOUNCE).await;
+
+				while let Ok(path) = receiver.try_recv() {
+					changed.insert(path);
+				}
+
+				let changed: Vec<PathBuf> = changed
+					.into_iter()
+					.filter(|path| paths.contains(path))
+					.collect();
+
+				if !changed.is_empty() {
+					let _ = output.send(Message::FilesChanged(changed)).await;
+				}
+			}
+		},
+	)
+}
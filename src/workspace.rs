@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use iced::widget::text_input;
+use ignore::WalkBuilder;
+
+use crate::Error;
+
+pub static PROMPT_ID: LazyLock<text_input::Id> = LazyLock::new(text_input::Id::unique);
+
+pub const SIDEBAR_WIDTH: f32 = 240.0;
+
+/// A folder opened in the sidebar, whose tree is loaded one folder at a time
+/// as it gets expanded.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+	pub root: PathBuf,
+	/// The contents of the folders that were loaded so far.
+	pub children: BTreeMap<PathBuf, Vec<Entry>>,
+	pub expanded: BTreeSet<PathBuf>,
+	pub selected: Option<PathBuf>,
+	/// The folder new entries are created in, which is the selected folder or
+	/// the folder of the selected file.
+	target: PathBuf,
+	pub prompt: Option<Prompt>,
+	/// Every file in the workspace, for quick open.
+	pub files: Vec<PathBuf>,
+	pub is_indexing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+	pub path: PathBuf,
+	pub name: String,
+	pub is_directory: bool,
+}
+
+/// A name being typed in the sidebar for a new or renamed entry.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+	pub kind: PromptKind,
+	pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum PromptKind {
+	NewFile(PathBuf),
+	NewFolder(PathBuf),
+	Rename(PathBuf),
+}
+
+/// A change to the files of the workspace.
+#[derive(Debug, Clone)]
+pub enum Operation {
+	Create {
+		path: PathBuf,
+		is_directory: bool,
+	},
+	Rename {
+		from: PathBuf,
+		to: PathBuf,
+	},
+	Delete(PathBuf),
+}
+
+impl Workspace {
+	pub fn new(root: PathBuf) -> Self {
+		Workspace {
+			expanded: BTreeSet::from([root.clone()]),
+			children: BTreeMap::new(),
+			selected: None,
+			target: root.clone(),
+			prompt: None,
+			files: Vec::new(),
+			// Every workspace gets indexed as soon as it is opened
+			is_indexing: true,
+			root,
+		}
+	}
+
+	/// The entries of the expanded folders in the order they are shown, along
+	/// with how deep they are nested.
+	pub fn visible(&self) -> Vec<(usize, &Entry)> {
+		let mut visible = Vec::new();
+
+		self.push_visible(&self.root, 0, &mut visible);
+
+		visible
+	}
+
+	fn push_visible<'a>(&'a self, directory: &Path, depth: usize, visible: &mut Vec<(usize, &'a Entry)>) {
+		for entry in self.children.get(directory).into_iter().flatten() {
+			visible.push((depth, entry));
+
+			if entry.is_directory && self.expanded.contains(&entry.path) {
+				self.push_visible(&entry.path, depth + 1, visible);
+			}
+		}
+	}
+
+	pub fn select(&mut self, path: PathBuf, is_directory: bool) {
+		self.target = if is_directory {
+			path.clone()
+		} else {
+			path.parent().map_or_else(|| self.root.clone(), Path::to_path_buf)
+		};
+
+		self.selected = Some(path);
+	}
+
+	pub fn target_directory(&self) -> PathBuf {
+		self.target.clone()
+	}
+
+	/// Forgets everything loaded below the path, after it was moved or deleted.
+	pub fn forget(&mut self, path: &Path) {
+		self.children.retain(|directory, _| !directory.starts_with(path));
+		self.expanded.retain(|directory| !directory.starts_with(path));
+		self.files.retain(|file| !file.starts_with(path));
+
+		if self.selected.as_ref().is_some_and(|selected| selected.starts_with(path)) {
+			self.selected = None;
+			self.target = self.root.clone();
+		}
+	}
+
+	/// Follows a rename, keeping the moved files in the index for quick open.
+	pub fn rename(&mut self, from: &Path, to: &Path) {
+		let moved: Vec<PathBuf> = self.files
+			.iter()
+			.filter_map(|file| moved(file, from, to))
+			.collect();
+
+		self.forget(from);
+		self.files.extend(moved);
+	}
+}
+
+/// Where a path ends up after `from` was renamed to `to`, if it was `from` or
+/// inside it.
+pub fn moved(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+	let rest = path.strip_prefix(from).ok()?;
+
+	// Joining an empty path would add a trailing separator
+	Some(if rest.as_os_str().is_empty() {
+		to.to_path_buf()
+	} else {
+		to.join(rest)
+	})
+}
+
+impl Prompt {
+	pub fn new(kind: PromptKind) -> Self {
+		let name = match &kind {
+			PromptKind::Rename(path) => path
+				.file_name()
+				.map(|name| name.to_string_lossy().to_string())
+				.unwrap_or_default(),
+			PromptKind::NewFile(_) | PromptKind::NewFolder(_) => String::new(),
+		};
+
+		Prompt { kind, name }
+	}
+
+	/// The operation the prompt asks for, or `None` if the name isn't valid.
+	pub fn operation(&self) -> Option<Operation> {
+		let name = self.name.trim();
+
+		if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+			return None;
+		}
+
+		Some(match &self.kind {
+			PromptKind::NewFile(directory) => Operation::Create {
+				path: directory.join(name),
+				is_directory: false,
+			},
+			PromptKind::NewFolder(directory) => Operation::Create {
+				path: directory.join(name),
+				is_directory: true,
+			},
+			PromptKind::Rename(path) => Operation::Rename {
+				from: path.clone(),
+				to: path.with_file_name(name),
+			},
+		})
+	}
+}
+
+impl Operation {
+	/// The folders whose contents change with the operation.
+	pub fn affected_directories(&self) -> Vec<PathBuf> {
+		let paths = match self {
+			Operation::Create { path, .. } | Operation::Delete(path) => vec![path],
+			Operation::Rename { from, to } => vec![from, to],
+		};
+
+		let mut directories: Vec<PathBuf> = paths
+			.into_iter()
+			.filter_map(|path| path.parent())
+			.map(Path::to_path_buf)
+			.collect();
+
+		directories.dedup();
+
+		directories
+	}
+}
+
+/// Lists a folder, leaving out the files ignored by `.gitignore` and similar
+/// files. Folders come first, and both are sorted by name.
+pub async fn read_directory(directory: PathBuf) -> Result<Vec<Entry>, Error> {
+	tokio::task::spawn_blocking(move || {
+		let mut entries: Vec<Entry> = WalkBuilder::new(&directory)
+			.max_depth(Some(1))
+			.hidden(false)
+			.require_git(false)
+			.filter_entry(|entry| entry.file_name() != ".git")
+			.build()
+			.filter_map(Result::ok)
+			.filter(|entry| entry.depth() == 1)
+			.map(|entry| Entry {
+				is_directory: entry.file_type().is_some_and(|file_type| file_type.is_dir()),
+				name: entry.file_name().to_string_lossy().to_string(),
+				path: entry.into_path(),
+			})
+			.collect();
+
+		entries.sort_by(|a, b| {
+			b.is_directory
+				.cmp(&a.is_directory)
+				.then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+		});
+
+		entries
+	})
+		.await
+		.map_err(|_| Error::IOFailed(io::ErrorKind::Other))
+}
+
+pub async fn apply(operation: Operation) -> Result<Operation, Error> {
+	let result = match &operation {
+		Operation::Create { path, is_directory: true } => tokio::fs::create_dir(path).await,
+		Operation::Create { path, is_directory: false } => tokio::fs::OpenOptions::new()
+			.write(true)
+			.create_new(true)
+			.open(path)
+			.await
+			.map(|_| ()),
+		Operation::Rename { from, to } => {
+			// Renaming would silently replace an existing file
+			if tokio::fs::symlink_metadata(to).await.is_ok() {
+				Err(io::Error::from(io::ErrorKind::AlreadyExists))
+			} else {
+				tokio::fs::rename(from, to).await
+			}
+		}
+		Operation::Delete(path) => {
+			if tokio::fs::symlink_metadata(path).await.is_ok_and(|metadata| metadata.is_dir()) {
+				tokio::fs::remove_dir_all(path).await
+			} else {
+				tokio::fs::remove_file(path).await
+			}
+		}
+	};
+
+	result
+		.map(|()| operation)
+		.map_err(|error| Error::IOFailed(error.kind()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn paths_follow_renamed_folders() {
+		let (from, to) = (Path::new("/project/old"), Path::new("/project/new"));
+
+		assert_eq!(moved(Path::new("/project/old/a/b.rs"), from, to), Some(PathBuf::from("/project/new/a/b.rs")));
+		assert_eq!(moved(from, from, to), Some(to.to_path_buf()));
+		assert_eq!(moved(Path::new("/project/older/b.rs"), from, to), None);
+	}
+}